@@ -2,38 +2,45 @@ use bevy::{pbr::NotShadowCaster, prelude::*};
 use bevy_rapier3d::prelude::CollisionGroups;
 
 use crate::{
-    constants::FABRICATOR_SPEED, player_system::gui_system::gui_startup::SelectedBuilding,
+    collision_layers::CollisionLayers,
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput},
+    },
+    settings::GameSettings,
 };
 
+use std::sync::Arc;
+
 use super::{
-    building::EntityQuery, building_components::*, buildings::BuildingReferenceComponent,
-    raycasting::BuildCursor, BlueprintFillMaterial, PipeCylinderMaterial,
+    building::EntityQuery,
+    building_components::*,
+    buildings::{Building, BuildingReferenceComponent, BuildingType},
+    raycasting::BuildCursor,
+    BlueprintFillMaterial, PipeCylinderMaterial,
 };
 
-const FABRICATOR_PER_UPDATE: u32 = (FABRICATOR_SPEED * 100) / 30;
+/// Sent when a blueprint has been completely filled and becomes a real building
+///
+/// `entity` is the entity with the `BuildingReferenceComponent`
+pub struct BuildingCompletedEvent {
+    pub entity: Entity,
+    pub building: Arc<Building>,
+}
 
 pub fn update_blueprints(
-    mut commands: Commands,
-
-    pipe_cylinder_query: EntityQuery<PipeCylinder>,
-
-    children_query: Query<&Children>,
-    mut groups_query: Query<&mut CollisionGroups>,
-    mut material_query: Query<&mut Handle<StandardMaterial>>,
-    building_ref_query: Query<&BuildingReferenceComponent>,
-    mut pb_query: Query<&mut PlacedBlueprint>,
-    mut pipe_bp_query: Query<&mut PipeBlueprint>,
     parent_query: Query<&Parent>,
+    mut pb_query: Query<&mut PlacedBlueprint>,
+    mut segment_bp_query: Query<&mut SegmentBlueprint>,
 
     build_cursor_res: Res<BuildCursor>,
     selected_building: Res<SelectedBuilding>,
-    (mouse_input, keyboard_input): (Res<Input<MouseButton>>, Res<Input<KeyCode>>),
-    bp_fill_materials: Res<BlueprintFillMaterial>,
-    pipe_cylinder_material: Res<PipeCylinderMaterial>,
+    input: ActionInput,
+    settings: Res<GameSettings>,
 ) {
     if build_cursor_res.intersection.is_some()
         && selected_building.id.is_none()
-        && mouse_input.pressed(MouseButton::Left)
+        && input.pressed(Action::Fabricate)
     {
         // Add portafab selected bool
         let entity = build_cursor_res.intersection.unwrap().0;
@@ -45,99 +52,152 @@ pub fn update_blueprints(
             Err(_) => return,
         };
 
-        let clicked_blueprint_result = pb_query.get_mut(parent);
+        // The fill and completion are handled by `apply_blueprint_progress` and `apply_segment_progress`
+        if let Ok(mut clicked_blueprint) = pb_query.get_mut(parent) {
+            clicked_blueprint.current += settings.fabricator_per_step();
+        }
+
+        // dis means you clicked a pipe (or a wall), its parts are children of the segment itself
+        let clicked_segment_result = parent_query
+            .get(parent)
+            .and_then(|e| segment_bp_query.get_mut(e.0));
 
-        if clicked_blueprint_result.is_ok() {
-            let mut clicked_blueprint = clicked_blueprint_result.unwrap();
-            let mut material = material_query.get_mut(parent).unwrap();
+        if let Ok(mut clicked_segment) = clicked_segment_result {
+            clicked_segment.current += settings.fabricator_per_step();
+        }
+    }
+}
 
-            clicked_blueprint.current += FABRICATOR_PER_UPDATE;
+/// Same as `apply_blueprint_progress` for two-point buildings, fills all their parts and turns the
+/// full ones into real buildings
+pub fn apply_segment_progress(
+    mut commands: Commands,
 
-            if clicked_blueprint.current >= clicked_blueprint.cost {
-                *material = building_ref_query
-                    .get(parent)
-                    .unwrap()
-                    .0
-                    .shape_data
-                    .material
-                    .clone()
-                    .unwrap();
+    segment_query: Query<
+        (
+            Entity,
+            &SegmentBlueprint,
+            &Children,
+            &BuildingReferenceComponent,
+        ),
+        Changed<SegmentBlueprint>,
+    >,
+    segment_piece_query: EntityQuery<SegmentPiece>,
+    children_query: Query<&Children>,
+    mut material_query: Query<&mut Handle<StandardMaterial>>,
 
-                let mut collision_groups = groups_query.get_mut(entity).unwrap();
-                *collision_groups = CollisionGroups::default();
+    bp_fill_materials: Res<BlueprintFillMaterial>,
+    pipe_cylinder_material: Res<PipeCylinderMaterial>,
+    mut completed_events: EventWriter<BuildingCompletedEvent>,
+) {
+    for (segment_bp, segment, segment_parts, building_ref) in segment_query.iter() {
+        if segment.current < segment.cost {
+            // Freshly placed segments keep the plain blueprint material
+            if segment.current > 0 {
+                let material_set =
+                    bp_fill_materials.get_bp_fill_material(segment.current, segment.cost);
 
-                commands
-                    .entity(parent)
-                    .remove_bundle::<(PlacedBlueprint, NotShadowCaster)>();
-            } else {
-                *material = bp_fill_materials
-                    .get_bp_fill_material(clicked_blueprint.current, clicked_blueprint.cost);
+                for part in segment_parts.iter() {
+                    if let Ok(mut mat) = material_query.get_mut(*part) {
+                        *mat = material_set.clone();
+                    }
+                }
             }
+            continue;
         }
 
-        let pipe_bp_result = parent_query.get(parent);
+        let segment_building = building_ref.0.clone();
+        let is_pipe = segment_building.building_id.building_type == BuildingType::Pipe;
+        let end_mat = segment_building.shape_data.material.clone().unwrap();
 
-        let clicked_pipe_result = match pipe_bp_result {
-            Ok(e) => pipe_bp_query.get_mut(e.0),
-            Err(e) => Result::Err(e),
-        };
+        for part in segment_parts.iter() {
+            let is_piece = segment_piece_query.contains(*part);
+
+            if let Ok(mut mat) = material_query.get_mut(*part) {
+                *mat = match (is_piece, is_pipe) {
+                    (true, true) => pipe_cylinder_material.0.clone(),
+                    _ => end_mat.clone(),
+                };
+            }
 
-        // dis means you clicked a pipe
-        if clicked_pipe_result.is_ok() {
-            let pipe_bp = pipe_bp_result.unwrap().0;
-            let mut clicked_pipe = clicked_pipe_result.unwrap();
-            clicked_pipe.current += FABRICATOR_PER_UPDATE;
-
-            let pipe_parts = children_query.get(pipe_bp).unwrap();
-
-            if clicked_pipe.current >= clicked_pipe.cost {
-                let pipe_base_mat = building_ref_query
-                    .get(pipe_bp)
-                    .unwrap()
-                    .0
-                    .shape_data
-                    .material
-                    .clone()
-                    .unwrap();
-
-                for part in pipe_parts.iter() {
-                    let mut mat = material_query.get_mut(*part).unwrap();
-                    if pipe_cylinder_query.contains(*part) {
-                        *mat = pipe_cylinder_material.0.clone();
-
-                        commands
-                            .entity(*part)
-                            .remove::<PipeCylinder>()
-                            .insert(CollisionGroups::default());
-
-                        commands
-                            .entity(children_query.get(*part).unwrap()[0])
-                            .insert(CollisionGroups::default());
-                    } else {
-                        *mat = pipe_base_mat.clone();
-
-                        // Remove useless marker components and activate collision
-                        commands
-                            .entity(*part)
-                            .remove_bundle::<(PipeFirst, PipeSecond)>()
-                            .insert(BuiltPipeEnd);
-
-                        commands
-                            .entity(children_query.get(*part).unwrap()[0])
-                            .insert(CollisionGroups::default());
+            match is_piece {
+                true => {
+                    commands
+                        .entity(*part)
+                        .remove::<SegmentPiece>()
+                        .insert(CollisionLayers::FINISHED_BUILDING.groups());
+                }
+                false => {
+                    // Remove useless marker components
+                    commands
+                        .entity(*part)
+                        .remove_bundle::<(SegmentFirst, SegmentSecond)>();
+
+                    if is_pipe {
+                        commands.entity(*part).insert(BuiltPipeEnd);
                     }
                 }
+            }
 
-                commands.entity(pipe_bp).remove::<PipeBlueprint>();
-            } else {
-                let material_set =
-                    bp_fill_materials.get_bp_fill_material(clicked_pipe.current, clicked_pipe.cost);
+            // And activate collision, the collider is the first child
+            if let Some(collider) = children_query.get(*part).ok().and_then(|e| e.first()) {
+                commands
+                    .entity(*collider)
+                    .insert(CollisionLayers::FINISHED_BUILDING.groups());
+            }
+        }
 
-                for part in pipe_parts.iter() {
-                    let mut mat = material_query.get_mut(*part).unwrap();
-                    *mat = material_set.clone();
-                }
+        commands.entity(segment_bp).remove::<SegmentBlueprint>();
+
+        completed_events.send(BuildingCompletedEvent {
+            entity: segment_bp,
+            building: segment_building,
+        });
+    }
+}
+
+/// Updates the fill material of blueprints that got iridium this frame (from the portafab or a drone)
+/// and turns the ones that are full into real buildings
+pub fn apply_blueprint_progress(
+    mut commands: Commands,
+
+    mut bp_query: Query<
+        (
+            Entity,
+            &PlacedBlueprint,
+            &Children,
+            &BuildingReferenceComponent,
+            &mut Handle<StandardMaterial>,
+        ),
+        Changed<PlacedBlueprint>,
+    >,
+    mut groups_query: Query<&mut CollisionGroups>,
+
+    bp_fill_materials: Res<BlueprintFillMaterial>,
+    mut completed_events: EventWriter<BuildingCompletedEvent>,
+) {
+    for (entity, blueprint, children, building_ref, mut material) in bp_query.iter_mut() {
+        if blueprint.current < blueprint.cost {
+            // Freshly placed blueprints keep the plain blueprint material
+            if blueprint.current > 0 {
+                *material =
+                    bp_fill_materials.get_bp_fill_material(blueprint.current, blueprint.cost);
             }
+            continue;
+        }
+
+        let building = building_ref.0.clone();
+        *material = building.shape_data.material.clone().unwrap();
+
+        // The collider is the first child
+        if let Ok(mut collision_groups) = groups_query.get_mut(children[0]) {
+            *collision_groups = CollisionLayers::FINISHED_BUILDING.groups();
         }
+
+        commands
+            .entity(entity)
+            .remove_bundle::<(PlacedBlueprint, NotShadowCaster)>();
+
+        completed_events.send(BuildingCompletedEvent { entity, building });
     }
 }
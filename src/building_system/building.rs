@@ -3,14 +3,14 @@ use std::{
     ops::{Add, Range},
 };
 
-use bevy::input::mouse::MouseWheel;
 pub use bevy::prelude::*;
 
 use crate::{
     algorithms::distance_vec3,
-    constants::{HALF_PI, PIPE_BASE_OFFSET, PIPE_CYLINDER_OFFSET},
+    constants::{HALF_PI, PIPE_CYLINDER_OFFSET},
     player_system::{
         gui_system::gui_startup::{GuiButtonId, SelectedBuilding},
+        input::{Action, ActionInput, AxisAction},
         player::CameraComp,
     },
 };
@@ -18,11 +18,12 @@ use crate::{
 use super::{
     building_components::*,
     building_functions::*,
-    buildings::{BuildingArcs, BuildingType, BuildingsResource},
+    buildings::{BuildingArcs, BuildingsResource},
     raycasting::BuildCursor,
     MaterialHandles,
 };
 
+/// Where both ends of a placed two-point building (pipe, wall) are, on its root entity
 #[derive(Component, Clone)]
 pub struct Pipe {
     pub pt_1: Transform,
@@ -34,6 +35,11 @@ impl Pipe {
         Pipe { pt_1, pt_2 }
     }
 
+    /// Halfway between both ends
+    pub fn midpoint(&self) -> Vec3 {
+        self.pt_1.translation.lerp(self.pt_2.translation, 0.5)
+    }
+
     pub fn cylinder_transform(&self) -> Transform {
         transform_between_points(
             self.pt_1
@@ -46,20 +52,20 @@ impl Pipe {
     }
 }
 
-enum PipeBools {
+enum SegmentBools {
     ClickFirstPointPlaced,
     ClickFirstPointNotPlaced,
     NoClickFirstPointPlaced,
     Other,
 }
 
-impl PipeBools {
-    fn match_bools(clicked: bool, hovered: bool, placed: bool) -> PipeBools {
+impl SegmentBools {
+    fn match_bools(clicked: bool, hovered: bool, placed: bool) -> SegmentBools {
         match (clicked, hovered, placed) {
-            (true, false, true) => PipeBools::ClickFirstPointPlaced,
-            (true, false, false) => PipeBools::ClickFirstPointNotPlaced,
-            (false, _, true) => PipeBools::NoClickFirstPointPlaced,
-            _ => PipeBools::Other,
+            (true, false, true) => SegmentBools::ClickFirstPointPlaced,
+            (true, false, false) => SegmentBools::ClickFirstPointNotPlaced,
+            (false, _, true) => SegmentBools::NoClickFirstPointPlaced,
+            _ => SegmentBools::Other,
         }
     }
 }
@@ -74,15 +80,15 @@ pub fn building(
     delete_query: EntityQuery<DeleteNextFrame>,
 
     (
-        pipe_prev_cylinder_query,
-        pipe_prev_cylinder_collider_query,
-        pipe_prev_placement_query,
-        pipe_prev_query,
+        segment_prev_piece_query,
+        segment_prev_piece_collider_query,
+        segment_prev_placement_query,
+        segment_prev_query,
     ): (
-        EntityQuery<PipePreviewCylinder>,
-        EntityQuery<PipePreviewCylinderCollider>,
-        EntityQuery<PipePreviewPlacement>,
-        EntityQuery<PipePreview>,
+        EntityQuery<SegmentPreviewPiece>,
+        EntityQuery<SegmentPreviewPieceCollider>,
+        EntityQuery<SegmentPreviewPlacement>,
+        EntityQuery<SegmentPreview>,
     ),
 
     camera_query: EntityQuery<CameraComp>,
@@ -98,15 +104,12 @@ pub fn building(
 
     gui_hover_query: Query<&Interaction, With<GuiButtonId>>,
 
-    (mouse_input, keyboard_input, building_arcs, buildings_res): (
-        Res<Input<MouseButton>>,
-        Res<Input<KeyCode>>,
+    (input, building_arcs, buildings_res): (
+        ActionInput,
         Res<BuildingArcs>,
         Res<BuildingsResource>,
     ),
 
-    mut mouse_scroll_event: EventReader<MouseWheel>,
-
     (mut transform_query, mut moved_query, mut visibility_query, mut building_rot_query): (
         Query<&mut Transform>,
         Query<&mut Moved>,
@@ -118,13 +121,11 @@ pub fn building(
         commands.entity(entity).despawn();
     }
 
-    if keyboard_input.pressed(KeyCode::LShift) {
-        for event in mouse_scroll_event.iter() {
-            bc_res.rotation += event.y * (PI / 16.0);
-        }
+    if input.pressed(Action::RotateModifier) {
+        bc_res.rotation += input.axis(AxisAction::RotateBuilding) * (PI / 16.0);
     }
 
-    if keyboard_input.just_pressed(KeyCode::R) {
+    if input.just_pressed(Action::Rotate) {
         bc_res.rotation += HALF_PI;
     }
 
@@ -163,8 +164,8 @@ pub fn building(
 
         let mut transform_cache = Transform::from_translation(translation).with_rotation(quat);
 
-        if selected_building_type == BuildingType::Pipe {
-            transform_cache = transform_cache.with_add_translation(*PIPE_BASE_OFFSET);
+        if let Some(segment) = &building.segment_data {
+            transform_cache = transform_cache.with_add_translation(segment.end_offset);
         }
 
         // check if we're hovering over the gui
@@ -226,50 +227,45 @@ pub fn building(
             }
         }
 
-        if mouse_input.just_pressed(MouseButton::Left) && !hovered {
+        if input.just_pressed(Action::Place) && !hovered {
             commands.entity(cbp_entity).insert(TryPlace);
         }
 
-        match selected_building_type {
-            // z offset for pipe cyl compared to pipe base: -0.0675
-            BuildingType::Pipe => {
-                // Rotate the offset and add it to the translation
-                let offset_transform = transform_cache.with_add_translation(*PIPE_CYLINDER_OFFSET);
-
-                let trans = offset_transform.translation;
-
-                if !pipe_prev_placement_query.is_empty() {
-                    let first_position = transform_query
-                        .get(pipe_prev_placement_query.single())
-                        .unwrap()
-                        .with_add_translation(*PIPE_CYLINDER_OFFSET)
-                        .translation;
-                    let transform = transform_query
-                        .get_many_mut([
-                            pipe_prev_cylinder_query.single(),
-                            pipe_prev_cylinder_collider_query.single(),
-                        ])
-                        .unwrap();
-                    update_pipe_cylinder_transform(transform, first_position, trans);
-                }
+        // Two-point buildings (pipes, walls) stretch a piece from the first end to the cursor
+        if let Some(segment) = &building.segment_data {
+            // Rotate the offset and add it to the translation
+            let offset_transform = transform_cache.with_add_translation(segment.offset);
+
+            let trans = offset_transform.translation;
+
+            if !segment_prev_placement_query.is_empty() {
+                let first_position = transform_query
+                    .get(segment_prev_placement_query.single())
+                    .unwrap()
+                    .with_add_translation(segment.offset)
+                    .translation;
+                let transform = transform_query
+                    .get_many_mut([
+                        segment_prev_piece_query.single(),
+                        segment_prev_piece_collider_query.single(),
+                    ])
+                    .unwrap();
+                update_segment_transform(transform, first_position, trans);
+            }
 
-                match PipeBools::match_bools(
-                    mouse_input.just_pressed(MouseButton::Left),
-                    hovered,
-                    !pipe_prev_placement_query.is_empty(),
-                ) {
-                    PipeBools::ClickFirstPointPlaced => {
-                        commands.entity(pipe_prev_query.single()).insert(TryPlace);
-                    }
-                    PipeBools::ClickFirstPointNotPlaced => {
-                        commands.entity(cbp_entity).insert(TryPlace);
-                    }
-                    _ => (),
+            match SegmentBools::match_bools(
+                input.just_pressed(Action::Place),
+                hovered,
+                !segment_prev_placement_query.is_empty(),
+            ) {
+                SegmentBools::ClickFirstPointPlaced => {
+                    commands.entity(segment_prev_query.single()).insert(TryPlace);
+                }
+                SegmentBools::ClickFirstPointNotPlaced => {
+                    commands.entity(cbp_entity).insert(TryPlace);
                 }
+                _ => (),
             }
-
-            // every other building
-            _ => {}
         }
     } else if selected_building.id.is_some() {
         match cursor_bp_query.get_single() {
@@ -281,7 +277,7 @@ pub fn building(
     }
 }
 
-fn update_pipe_cylinder_transform(
+fn update_segment_transform(
     mut transform: [Mut<Transform>; 2],
 
     first_pos: Vec3,
@@ -290,17 +286,31 @@ fn update_pipe_cylinder_transform(
     let mut set_transform = transform_between_points(first_pos, second_pos);
     set_transform.scale.y = set_transform.scale.y.max(0.001);
 
-    let (cylinder, collider) = transform.split_at_mut(1);
+    let (piece, collider) = transform.split_at_mut(1);
 
-    *cylinder[0] = set_transform;
+    *piece[0] = set_transform;
     *collider[0] = set_transform;
 }
 
-fn transform_between_points(a: Vec3, b: Vec3) -> Transform {
+/// Returns the transform that stretches something 1 unit long on its Y axis from `a` to `b`
+///
+/// The local X axis is kept level, so the local Z axis points as far up as it can (walls stay upright)
+pub fn transform_between_points(a: Vec3, b: Vec3) -> Transform {
     let translation = (a + b) / 2.0;
-    let rotation = Quat::from_rotation_arc(Vec3::Y, (a - b).normalize());
     let distance = distance_vec3(a, b);
 
+    let y_axis = (a - b).normalize_or_zero();
+    let x_axis = match y_axis.cross(Vec3::Y).try_normalize() {
+        Some(e) => e,
+        // Straight up or down (or zero length), any level axis works
+        None => Vec3::X,
+    };
+    let z_axis = x_axis.cross(y_axis);
+    let rotation = match y_axis == Vec3::ZERO {
+        true => Quat::IDENTITY,
+        false => Quat::from_mat3(&Mat3::from_cols(x_axis, y_axis, z_axis)),
+    };
+
     Transform::from_translation(translation)
         .with_rotation(rotation)
         .with_scale(Vec3::new(1.0, distance, 1.0))
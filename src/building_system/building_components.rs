@@ -16,18 +16,21 @@ pub struct PipePlacement {
     pub transform: Option<Transform>,
 }
 
-/// The entity with this component is the pipe cylinder between the two endpoints **before** it is placed
+/// The entity with this component is the stretched piece (pipe cylinder, wall panel) between the two endpoints
+/// of a two-point building **before** it is placed
 #[derive(Component)]
-pub struct PipePreviewCylinder;
+pub struct SegmentPreviewPiece;
 
 #[derive(Component)]
-pub struct PipePreviewCylinderCollider;
+pub struct SegmentPreviewPieceCollider;
 
+/// The first endpoint of a two-point building **before** it is placed
 #[derive(Component)]
-pub struct PipePreviewPlacement;
+pub struct SegmentPreviewPlacement;
 
+/// The root entity of a two-point building **before** it is placed, the ends and piece are its children
 #[derive(Component)]
-pub struct PipePreview;
+pub struct SegmentPreview;
 
 #[derive(Component)]
 pub struct TestComponent;
@@ -51,21 +54,22 @@ pub struct PlacedBlueprint {
     pub current: u32,
 }
 
+/// Two-point buildings (pipes, walls) that have yet to be filled, the cost depends on their length
 #[derive(Component)]
-pub struct PipeBlueprint {
+pub struct SegmentBlueprint {
     pub cost: u32,
     pub current: u32,
     // connections.. will add later
 }
 
 #[derive(Component)]
-pub struct PipeFirst;
+pub struct SegmentFirst;
 
 #[derive(Component)]
-pub struct PipeSecond;
+pub struct SegmentSecond;
 
 #[derive(Component)]
-pub struct PipeCylinder;
+pub struct SegmentPiece;
 
 /// Entities with this component have `true` when they are moved
 #[derive(Component)]
@@ -78,6 +82,20 @@ pub enum Placeable {
     No,
 }
 
+/// Why the cursor blueprint can't be placed where it is
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Obstruction {
+    None,
+    /// Intersecting something, or not snapped to something it has to be snapped to
+    Colliding,
+    /// Too far away from the keep
+    OutOfTerritory,
+    /// There can't be any more of this building
+    Limit,
+    /// The piece between the ends of a two-point building is too long or too steep
+    InvalidSpan,
+}
+
 /// Adding this component to the cursor blueprint will mark it for trying to place it after collisions are calculated and checked
 #[derive(Component)]
 pub struct TryPlace;
@@ -88,6 +106,10 @@ pub struct BuiltPipeEnd;
 #[derive(Component)]
 pub struct BuildingRotation(pub f32);
 
+/// The building that the cursor blueprint (or a pipe end made from it) is snapped to
+#[derive(Component, Clone, Copy)]
+pub struct SnappedTo(pub Entity);
+
 impl Debug for BuildingReferenceComponent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_tuple("BuildingReferenceComponent")
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, bail};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadState, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::BoxedFuture,
+};
+use bevy_rapier3d::prelude::Collider;
+use serde::Deserialize;
+
+use super::buildings::{
+    Building, BuildingIO, BuildingId, BuildingIridiumData, BuildingSegmentData, BuildingShapeData,
+    BuildingSnapData, BuildingType,
+};
+
+/// Folder (in `assets`) every `*.building.ron` file is loaded from
+pub const BUILDING_DEFINITIONS_FOLDER: &str = "data/buildings";
+
+/// A building as it's written in its `*.building.ron` file
+///
+/// Iridium amounts are in hundredths, angles are in degrees
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "0b6f3a8e-1d2c-4f7a-9e5b-7c4d2a1f8e36"]
+pub struct BuildingDefinition {
+    /// Must be unique between all files
+    pub id: BuildingType,
+    pub name: String,
+    pub io: BuildingIO,
+    pub storage: Option<u32>,
+    pub current: Option<u32>,
+    pub generation: Option<u32>,
+    pub cost: u32,
+    pub health: u32,
+    /// GLTF of the building, its first mesh is used
+    pub mesh_path: String,
+    pub collider: ColliderDefinition,
+    pub collider_offset: [f32; 3],
+    #[serde(default)]
+    pub snapping: Vec<SnapDefinition>,
+    #[serde(default)]
+    pub segment: Option<SegmentDefinition>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub enum ColliderDefinition {
+    Cuboid { half_extents: [f32; 3] },
+    Cylinder { half_height: f32, radius: f32 },
+    Ball { radius: f32 },
+    Compound { parts: Vec<ColliderPart> },
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ColliderPart {
+    pub position: [f32; 3],
+    pub shape: ColliderDefinition,
+}
+
+/// Where one other building can snap to this one
+#[derive(Deserialize, Clone, Debug)]
+pub struct SnapDefinition {
+    pub building: BuildingType,
+    pub sockets: Vec<SnapSocket>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SnapSocket {
+    pub position: [f32; 3],
+    pub axis: [f32; 3],
+    /// Range the snapped building can be rotated in around `axis`
+    pub rotation: (f32, f32),
+}
+
+/// See `BuildingSegmentData`
+#[derive(Deserialize, Clone, Debug)]
+pub struct SegmentDefinition {
+    pub mesh_path: String,
+    pub collider: ColliderDefinition,
+    pub offset: [f32; 3],
+    pub end_offset: [f32; 3],
+    pub cost_per_unit: u32,
+    #[serde(default)]
+    pub max_length: Option<f32>,
+    #[serde(default)]
+    pub max_slope: Option<f32>,
+}
+
+impl ColliderDefinition {
+    /// `field` is where this collider is in the file, used in the error
+    pub fn validate(&self, field: &str) -> Result<(), String> {
+        let positive = |name: &str, value: f32| match value > 0.0 {
+            true => Ok(()),
+            false => Err(format!("field `{}.{}`: must be more than 0", field, name)),
+        };
+
+        match self {
+            ColliderDefinition::Cuboid { half_extents } => half_extents
+                .iter()
+                .try_for_each(|e| positive("half_extents", *e)),
+            ColliderDefinition::Cylinder {
+                half_height,
+                radius,
+            } => positive("half_height", *half_height).and(positive("radius", *radius)),
+            ColliderDefinition::Ball { radius } => positive("radius", *radius),
+            ColliderDefinition::Compound { parts } => {
+                if parts.is_empty() {
+                    return Err(format!("field `{}.parts`: needs at least one part", field));
+                }
+                parts.iter().enumerate().try_for_each(|(i, part)| {
+                    part.shape
+                        .validate(&format!("{}.parts[{}].shape", field, i))
+                })
+            }
+        }
+    }
+
+    pub fn to_collider(&self) -> Collider {
+        match self {
+            ColliderDefinition::Cuboid { half_extents } => {
+                Collider::cuboid(half_extents[0], half_extents[1], half_extents[2])
+            }
+            ColliderDefinition::Cylinder {
+                half_height,
+                radius,
+            } => Collider::cylinder(*half_height, *radius),
+            ColliderDefinition::Ball { radius } => Collider::ball(*radius),
+            ColliderDefinition::Compound { parts } => Collider::compound(
+                parts
+                    .iter()
+                    .map(|e| {
+                        (
+                            Vec3::from(e.position),
+                            Quat::IDENTITY,
+                            e.shape.to_collider(),
+                        )
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl BuildingDefinition {
+    /// Checks everything the types can't, the error names the field that's wrong
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("field `name`: can't be empty".to_string());
+        }
+        if let (Some(current), Some(storage)) = (self.current, self.storage) {
+            if current > storage {
+                return Err("field `current`: can't be more than `storage`".to_string());
+            }
+        }
+        if self.current.is_some() && self.storage.is_none() {
+            return Err("field `current`: needs `storage`".to_string());
+        }
+        if !self.mesh_path.ends_with(".gltf") {
+            return Err("field `mesh_path`: must be a .gltf file".to_string());
+        }
+        self.collider.validate("collider")?;
+
+        for (i, snap) in self.snapping.iter().enumerate() {
+            if snap.sockets.is_empty() {
+                return Err(format!(
+                    "field `snapping[{}].sockets`: needs at least one socket",
+                    i
+                ));
+            }
+            for (j, socket) in snap.sockets.iter().enumerate() {
+                if Vec3::from(socket.axis).length_squared() == 0.0 {
+                    return Err(format!(
+                        "field `snapping[{}].sockets[{}].axis`: can't be zero",
+                        i, j
+                    ));
+                }
+                if socket.rotation.0 > socket.rotation.1 {
+                    return Err(format!(
+                        "field `snapping[{}].sockets[{}].rotation`: start is after end",
+                        i, j
+                    ));
+                }
+            }
+        }
+
+        if let Some(segment) = &self.segment {
+            if segment.mesh_path.is_empty() {
+                return Err("field `segment.mesh_path`: can't be empty".to_string());
+            }
+            segment.collider.validate("segment.collider")?;
+            if matches!(segment.max_length, Some(e) if e <= 0.0) {
+                return Err("field `segment.max_length`: must be more than 0".to_string());
+            }
+            if matches!(segment.max_slope, Some(e) if e <= 0.0 || e > 90.0) {
+                return Err(
+                    "field `segment.max_slope`: must be more than 0 and at most 90".to_string(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The building this describes, its mesh still has to be loaded with `BuildingShapeData::load_from_path`
+    pub fn to_building(&self) -> Building {
+        Building {
+            building_id: BuildingId {
+                building_type: self.id.clone(),
+                building_name: self.name.clone(),
+            },
+            health: self.health,
+            iridium_data: BuildingIridiumData {
+                io: self.io.clone(),
+                storage: self.storage,
+                current: self.current,
+                generation: self.generation,
+                cost: self.cost,
+            },
+            shape_data: BuildingShapeData {
+                mesh: None,
+                material: None,
+                path: self.mesh_path.clone(),
+                collider: self.collider.to_collider(),
+                collider_offset: Vec3::from(self.collider_offset),
+            },
+            snap_data: BuildingSnapData {
+                buildings: self.snapping.iter().map(|e| e.building.clone()).collect(),
+                transform: self
+                    .snapping
+                    .iter()
+                    .map(|e| {
+                        e.sockets
+                            .iter()
+                            .map(|e| (Vec3::from(e.position), Vec3::from(e.axis).normalize()))
+                            .collect()
+                    })
+                    .collect(),
+                rotation_allowed: self
+                    .snapping
+                    .iter()
+                    .map(|e| {
+                        e.sockets
+                            .iter()
+                            .map(|e| e.rotation.0.to_radians()..e.rotation.1.to_radians())
+                            .collect()
+                    })
+                    .collect(),
+            },
+            segment_data: self.segment.as_ref().map(|e| BuildingSegmentData {
+                mesh_path: e.mesh_path.clone(),
+                collider: e.collider.to_collider(),
+                offset: Vec3::from(e.offset),
+                end_offset: Vec3::from(e.end_offset),
+                cost_per_unit: e.cost_per_unit,
+                max_length: e.max_length,
+                max_slope: e.max_slope.map(f32::to_radians),
+            }),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct BuildingDefinitionLoader;
+
+impl AssetLoader for BuildingDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let definition: BuildingDefinition = ron::de::from_bytes(bytes)
+                .map_err(|e| anyhow!("{:?}: {}", load_context.path(), e))?;
+
+            if let Err(e) = definition.validate() {
+                bail!("{:?}: {}", load_context.path(), e);
+            }
+
+            load_context.set_default_asset(LoadedAsset::new(definition));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["building.ron"]
+    }
+}
+
+/// Every file in `BUILDING_DEFINITIONS_FOLDER`
+pub struct BuildingDefinitionHandles(pub Vec<Handle<BuildingDefinition>>);
+
+pub fn load_building_definitions(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = match asset_server.load_folder(BUILDING_DEFINITIONS_FOLDER) {
+        Ok(e) => e.into_iter().map(|e| e.typed()).collect(),
+        Err(e) => {
+            error!(
+                "Could not load building definitions from {:?}: {}",
+                BUILDING_DEFINITIONS_FOLDER, e
+            );
+            Vec::new()
+        }
+    };
+
+    commands.insert_resource(BuildingDefinitionHandles(handles));
+}
+
+/// `false` while any definition is still loading, ones that failed count as done (the loader already
+/// logged why)
+pub fn building_definitions_loaded(
+    asset_server: &AssetServer,
+    handles: &BuildingDefinitionHandles,
+) -> bool {
+    handles.0.iter().all(|e| {
+        matches!(
+            asset_server.get_load_state(e),
+            LoadState::Loaded | LoadState::Failed
+        )
+    })
+}
@@ -4,6 +4,8 @@ use bevy::{pbr::NotShadowCaster, prelude::*};
 
 use bevy_rapier3d::{plugin::RapierContext, prelude::*};
 
+use crate::collision_layers::{CollisionLayer, CollisionLayers};
+
 use super::{
     building_components::*,
     buildings::{Building, BuildingReferenceComponent},
@@ -53,10 +55,7 @@ pub fn spawn_cursor_bp(
                     .spawn()
                     .insert_bundle((
                         collider,
-                        CollisionGroups {
-                            memberships: 0b00010000,
-                            filters: 0b11110111,
-                        },
+                        CollisionLayers::of(CollisionLayer::Cursor).groups(),
                         transform.with_add_translation(collider_offset),
                         Sensor(true),
                         ActiveCollisionTypes::all(),
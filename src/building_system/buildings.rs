@@ -3,37 +3,70 @@ use core::hash::Hash;
 use std::{ops::Range, sync::Arc};
 
 use bevy::{
-    ecs::schedule::ShouldRun, gltf::GltfMesh, math::Vec3, pbr::StandardMaterial, prelude::*,
-    utils::HashMap,
+    asset::HandleId, ecs::schedule::ShouldRun, gltf::GltfMesh, math::Vec3, pbr::StandardMaterial,
+    prelude::*, utils::HashMap,
 };
 use bevy_rapier3d::prelude::Collider;
+use serde::{Deserialize, Serialize};
 
-use crate::{constants::GLOBAL_PIPE_ID, model_loader::combine_gltf_mesh};
-
-use lazy_static::lazy_static;
+use crate::{
+    constants::GLOBAL_PIPE_ID, localization::Localization, model_loader::combine_gltf_mesh,
+};
 
-use super::{load_models::get_load_states, ModelHandles};
+use super::{
+    building_definitions::{
+        building_definitions_loaded, BuildingDefinition, BuildingDefinitionHandles,
+    },
+    load_models::ModelManifest,
+};
 
-#[derive(Hash, PartialEq, Eq, Clone, Debug)]
+#[derive(Hash, PartialEq, Eq, Clone, Debug, Serialize, Deserialize)]
 pub enum BuildingType {
     Wellpump,
     Pipe,
+    ShieldGenerator,
+    Arsenal,
+    Garage,
+    Ssim,
+    Wall,
+    Gate,
+    Keep,
+    Bridge,
+    LadderBlock,
+    Seat,
+    BoostPad,
+    SpawnPoint,
+    Transceiver,
+    SpatialAnchor,
 }
 
 #[derive(Clone)]
 pub struct Building {
     pub building_id: BuildingId,
+    pub health: u32,
     pub iridium_data: BuildingIridiumData,
     pub shape_data: BuildingShapeData,
     pub snap_data: BuildingSnapData,
+    pub segment_data: Option<BuildingSegmentData>,
 }
 
 #[derive(Clone)]
 pub struct BuildingId {
     pub building_type: BuildingType,
+    /// Shown if the string table doesn't have `building.<id>`
     pub building_name: String,
 }
 
+impl BuildingId {
+    /// Name of the building in the selected language
+    pub fn name<'a>(&'a self, localization: &'a Localization) -> &'a str {
+        localization.get_or(
+            &format!("building.{}", building_enum_to_string(&self.building_type)),
+            &self.building_name,
+        )
+    }
+}
+
 #[derive(Clone)]
 pub struct BuildingIridiumData {
     pub io: BuildingIO,
@@ -43,7 +76,7 @@ pub struct BuildingIridiumData {
     pub cost: u32,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Deserialize)]
 pub enum BuildingIO {
     None,
     In,
@@ -77,12 +110,63 @@ pub struct BuildingSnapData {
     pub rotation_allowed: Vec<Vec<Range<f32>>>,
 }
 
+/// Buildings with this are placed with two clicks, like pipes and walls
+///
+/// Each click places an end (the building's own mesh) and a piece is stretched between both ends
+#[derive(Clone)]
+pub struct BuildingSegmentData {
+    /// Mesh of the piece, it has to be 1 unit long on its Y axis
+    pub mesh_path: String,
+
+    /// Collider of the piece, stretched along with the mesh
+    pub collider: Collider,
+
+    /// Where the piece starts/ends relative to each end
+    pub offset: Vec3,
+
+    /// Where the ends go relative to the cursor
+    pub end_offset: Vec3,
+
+    /// Extra cost per unit of length of the piece, on top of the building's cost
+    pub cost_per_unit: u32,
+
+    /// Longest the piece can be
+    pub max_length: Option<f32>,
+
+    /// Steepest the piece can be, in radians from level
+    pub max_slope: Option<f32>,
+}
+
+impl BuildingSegmentData {
+    /// Checks if a piece going along `direction` (normalized) for `length` is too long or steep
+    pub fn span_allowed(&self, length: f32, direction: Vec3) -> bool {
+        let slope = direction.y.abs().min(1.0).asin();
+
+        self.max_length.map_or(true, |e| length <= e) && self.max_slope.map_or(true, |e| slope <= e)
+    }
+}
+
+impl Building {
+    /// Cost of the building, including the length of the piece if it has one
+    pub fn cost_with_length(&self, length: f32) -> u32 {
+        match &self.segment_data {
+            Some(segment) => {
+                self.iridium_data.cost + (segment.cost_per_unit as f32 * length).round() as u32
+            }
+            None => self.iridium_data.cost,
+        }
+    }
+}
+
 /// Contains all of the buildings in a hashmap
 pub struct BuildingsResource(pub HashMap<BuildingType, Building>);
 
 /// Contains a reference to every building
 pub struct BuildingArcs(pub HashMap<BuildingType, Arc<Building>>);
 
+/// The building each definition file made, to know what an edit to the file changes
+pub struct BuildingDefinitionIds(pub HashMap<HandleId, BuildingType>);
+
 /// Building initialization done
 pub struct BuildingInitDone(pub bool);
 
@@ -120,120 +204,6 @@ impl<T, U, V, W> Pipe<T, U, V, W> {
     }
 }
 
-macro_rules! Building {
-    (
-        Type: $buildingtype:ident,
-        Name: $name:literal,
-        Flow: $flow:ident,
-        Storage: $storage:expr,
-        Current: $current:expr,
-        Generation: $generation:expr,
-        Cost: $cost:literal,
-        MeshPath: $meshtype:literal,
-        Collider: $coll:expr
-    ) => {
-        Building {
-            building_id: BuildingId {
-                building_type: BuildingType::$buildingtype,
-                building_name: $name.to_string(),
-            },
-            iridium_data: BuildingIridiumData {
-                io: BuildingIO::$flow,
-                storage: match $storage {
-                    -1 => None,
-                    _ => Some($storage as u32)
-                },
-                current:  match $current {
-                    -1 => None,
-                    _ => Some($current as u32)
-                },
-                generation:  match $generation {
-                    -1 => None,
-                    _ => Some($generation as u32)
-                },
-                cost: $cost,
-            },
-            shape_data: BuildingShapeData {
-                mesh: None,
-                material: None,
-                path: $meshtype.to_string(),
-                collider: $coll.coll,
-                collider_offset: $coll.trans,
-            },
-            snap_data: BuildingSnapData {
-                buildings: Vec::new(),
-                transform: Vec::new(),
-                rotation_allowed: Vec::new(),
-            }
-        }
-    };
-
-    (
-        Type: $buildingtype:ident,
-        Name: $name:literal,
-        Flow: $flow:ident,
-        Storage: $storage:expr,
-        Current: $current:expr,
-        Generation: $generation:expr,
-        Cost: $cost:literal,
-        MeshPath: $meshtype:literal,
-        Collider: $coll:expr,
-        Snapping:
-        $((
-            Building: $snap_building:expr,
-            Positions: $($snap_position:expr),+;
-            Axis: $($snap_axis:expr),+;
-            RotationAllowed: $($snap_rotation:expr),+;
-        )),+
-    ) => {
-        Building {
-            building_id: BuildingId {
-                building_type: BuildingType::$buildingtype,
-                building_name: $name.to_string(),
-            },
-            iridium_data: BuildingIridiumData {
-                io: BuildingIO::$flow,
-                storage: match $storage {
-                    -1 => None,
-                    _ => Some($storage as u32)
-                },
-                current:  match $current {
-                    -1 => None,
-                    _ => Some($current as u32)
-                },
-                generation:  match $generation {
-                    -1 => None,
-                    _ => Some($generation as u32)
-                },
-                cost: $cost,
-            },
-            shape_data: BuildingShapeData {
-                mesh: None,
-                material: None,
-                path: $meshtype.to_string(),
-                collider: $coll.coll,
-                collider_offset: $coll.trans,
-            },
-            snap_data: BuildingSnapData {
-                buildings: vec![$($snap_building),+],
-                transform: {
-                    let pos = vec![$(vec![$($snap_position),+]);+];
-                    let axis = vec![$(vec![$($snap_axis),+]);+];
-
-                    let mut return_vec = Vec::new();
-
-                    for (p, a) in pos.iter().zip(axis.iter()) {
-                        return_vec.push(p.iter().copied().zip(a.iter().copied()).collect());
-                    }
-
-                    return_vec
-                },
-                rotation_allowed: vec![$(vec![$($snap_rotation),+]);+],
-            }
-        }
-    }
-}
-
 impl BuildingShapeData {
     /// "Loads" the GLTF from the path and replaces the default `None`s in the struct with the stuff that's supposed to go there
     ///
@@ -257,55 +227,17 @@ impl BuildingShapeData {
     }
 }
 
-pub fn load_buildings_into_resource(mut commands: Commands) {
-    info!("into resource start");
-    let mut hash = HashMap::with_capacity(2);
-
-    hash.insert_no_return(
-        BuildingType::Wellpump,
-        Building!(
-            Type: Wellpump,
-            Name: "Well Pump",
-            Flow: InOut,
-            Storage: 50_00,
-            Current: 0,
-            Generation: 5_00,
-            Cost: 100_00,
-            MeshPath: "models/buildings/well_pump.gltf",
-            Collider: WELLPUMP_COLLIDER.clone(),
-            Snapping: (
-                Building: BuildingType::Pipe,
-                Positions: Vec3::new(0.0, 0.453448, 0.747605);
-                Axis: Vec3::Y;
-                RotationAllowed: 0.0..0.0;
-            )
-
-        ),
-    )
-    .insert_no_return(
-        BuildingType::Pipe,
-        Building!(
-            Type: Pipe,
-            Name: "Pipe",
-            Flow: InOut,
-            Storage: 0,
-            Current: 0,
-            Generation: 0,
-            Cost: 10_00,
-            MeshPath: "models/pipes/pipe_base.gltf",
-            Collider: PIPE_COLLIDER.clone()
-        ),
-    );
-
-    commands.insert_resource(BuildingsResource(hash));
-    info!("into resource done");
-}
-
+/// Turns the loaded definitions into `BuildingsResource` and `BuildingArcs`
+///
+/// Definitions that failed to load are left out, if two files define the same building only the first
+/// one is kept
 pub fn load_buildings_in_resource(
     mut commands: Commands,
 
-    mut buildings_res: ResMut<BuildingsResource>,
     mut building_init_done: ResMut<BuildingInitDone>,
+    definition_handles: Res<BuildingDefinitionHandles>,
+    definitions: Res<Assets<BuildingDefinition>>,
+    model_manifest: Res<ModelManifest>,
 
     asset_server: Res<AssetServer>,
     gltf_meshes: ResMut<Assets<GltfMesh>>,
@@ -317,9 +249,35 @@ pub fn load_buildings_in_resource(
         return;
     }
 
-    let mut hash = HashMap::with_capacity(2);
+    let mut buildings = HashMap::with_capacity(definition_handles.0.len());
+    let mut paths = HashMap::with_capacity(definition_handles.0.len());
+    let mut ids = HashMap::with_capacity(definition_handles.0.len());
+
+    for handle in definition_handles.0.iter() {
+        let definition = match definitions.get(handle) {
+            Some(e) => e,
+            None => continue,
+        };
+        let path = asset_server.get_handle_path(handle);
+
+        if let Some(first) = paths.get(&definition.id) {
+            error!(
+                "{:?}: field `id`: {:?} is already defined in {:?}",
+                path, definition.id, first
+            );
+            continue;
+        }
+        if !model_manifest.is_loaded(&definition.mesh_path) {
+            error!(
+                "{:?}: field `mesh_path`: {:?} could not be loaded",
+                path, definition.mesh_path
+            );
+            continue;
+        }
+        paths.insert(definition.id.clone(), path);
+        ids.insert(handle.id, definition.id.clone());
 
-    for (typ, building) in buildings_res.0.iter_mut() {
+        let mut building = definition.to_building();
         building.shape_data.load_from_path(
             &asset_server,
             &gltf_meshes,
@@ -327,21 +285,63 @@ pub fn load_buildings_in_resource(
             &mut materials,
             &mut images,
         );
-        hash.insert_no_return(typ.clone(), Arc::new(building.clone()));
+        buildings.insert(definition.id.clone(), building);
     }
+    info!("loaded {} building definitions", buildings.len());
 
-    commands.insert_resource(BuildingArcs(hash));
+    let arcs = buildings
+        .iter()
+        .map(|(typ, building)| (typ.clone(), Arc::new(building.clone())))
+        .collect();
+
+    commands.insert_resource(BuildingsResource(buildings));
+    commands.insert_resource(BuildingArcs(arcs));
+    commands.insert_resource(BuildingDefinitionIds(ids));
     building_init_done.0 = true;
 }
 
-/// Tries to match `name` to a building
-///
-/// Panics if `name` does not match any building
-pub fn string_to_building_enum(name: String) -> BuildingType {
-    match name.as_str() {
-        "Well Pump" => BuildingType::Wellpump,
-        "Pipe" => BuildingType::Pipe,
-        _ => panic!("Could not match \"{}\" to any building", name),
+/// Tries to match the id of a build menu item to a building
+pub fn string_to_building_enum(id: String) -> Option<BuildingType> {
+    Some(match id.as_str() {
+        "well_pump" => BuildingType::Wellpump,
+        "pipe" => BuildingType::Pipe,
+        "shield_generator" => BuildingType::ShieldGenerator,
+        "arsenal" => BuildingType::Arsenal,
+        "garage" => BuildingType::Garage,
+        "ssim" => BuildingType::Ssim,
+        "wall" => BuildingType::Wall,
+        "gate" => BuildingType::Gate,
+        "keep" => BuildingType::Keep,
+        "bridge" => BuildingType::Bridge,
+        "ladder_block" => BuildingType::LadderBlock,
+        "seat" => BuildingType::Seat,
+        "boost_pad" => BuildingType::BoostPad,
+        "spawn_point" => BuildingType::SpawnPoint,
+        "transceiver" => BuildingType::Transceiver,
+        "spatial_anchor" => BuildingType::SpatialAnchor,
+        _ => return None,
+    })
+}
+
+/// Id of a building in the build menu and string tables, the reverse of `string_to_building_enum`
+pub fn building_enum_to_string(building_type: &BuildingType) -> &'static str {
+    match building_type {
+        BuildingType::Wellpump => "well_pump",
+        BuildingType::Pipe => "pipe",
+        BuildingType::ShieldGenerator => "shield_generator",
+        BuildingType::Arsenal => "arsenal",
+        BuildingType::Garage => "garage",
+        BuildingType::Ssim => "ssim",
+        BuildingType::Wall => "wall",
+        BuildingType::Gate => "gate",
+        BuildingType::Keep => "keep",
+        BuildingType::Bridge => "bridge",
+        BuildingType::LadderBlock => "ladder_block",
+        BuildingType::Seat => "seat",
+        BuildingType::BoostPad => "boost_pad",
+        BuildingType::SpawnPoint => "spawn_point",
+        BuildingType::Transceiver => "transceiver",
+        BuildingType::SpatialAnchor => "spatial_anchor",
     }
 }
 
@@ -356,35 +356,49 @@ pub fn building_init_done(b: Res<BuildingInitDone>) -> ShouldRun {
 pub fn building_init_not_done_and_get_load_states(
     b: Res<BuildingInitDone>,
     asset_server: Res<AssetServer>,
-    model_handles: Res<ModelHandles>,
+    model_manifest: Res<ModelManifest>,
+    definition_handles: Res<BuildingDefinitionHandles>,
+    definitions: Res<Assets<BuildingDefinition>>,
 ) -> ShouldRun {
-    let load_state = get_load_states(asset_server, model_handles);
-    if load_state == ShouldRun::NoAndCheckAgain {
-        ShouldRun::NoAndCheckAgain
-    } else if b.0 {
-        ShouldRun::No
-    } else {
-        ShouldRun::YesAndCheckAgain
+    if b.0 {
+        return ShouldRun::No;
+    }
+    if !building_definitions_loaded(&asset_server, &definition_handles) {
+        return ShouldRun::NoAndCheckAgain;
     }
-}
 
-#[derive(Clone)]
-struct CollTransform {
-    coll: Collider,
-    trans: Vec3,
+    // The models of new definitions are only requested once they're loaded
+    let requested = definition_handles
+        .0
+        .iter()
+        .filter_map(|e| definitions.get(e))
+        .all(|e| model_manifest.contains(&e.mesh_path));
+
+    if requested && model_manifest.is_done() {
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::NoAndCheckAgain
+    }
 }
 
-impl CollTransform {
-    fn from_collider(coll: Collider) -> Self {
-        CollTransform {
-            coll,
-            trans: Vec3::ZERO,
-        }
+/// Adds the model of every loaded building definition to the manifest
+pub fn request_building_models(
+    asset_server: Res<AssetServer>,
+    building_init_done: Res<BuildingInitDone>,
+    definition_handles: Res<BuildingDefinitionHandles>,
+    definitions: Res<Assets<BuildingDefinition>>,
+    mut model_manifest: ResMut<ModelManifest>,
+) {
+    if building_init_done.0 {
+        return;
     }
 
-    fn with_translation(&mut self, trans: Vec3) -> Self {
-        self.trans = trans;
-        self.to_owned()
+    for definition in definition_handles
+        .0
+        .iter()
+        .filter_map(|e| definitions.get(e))
+    {
+        model_manifest.request(&definition.mesh_path, &asset_server);
     }
 }
 
@@ -402,11 +416,50 @@ where
     }
 }
 
-lazy_static! {
-    static ref WELLPUMP_COLLIDER: CollTransform =
-        CollTransform::from_collider(Collider::cylinder(1.11928 / 2.0, 0.89528))
-            .with_translation(Vec3::new(0.0, 0.569639, -0.05));
-    static ref PIPE_COLLIDER: CollTransform =
-        CollTransform::from_collider(Collider::cuboid(0.27 / 2.0, 0.27 / 2.0, 0.3025 / 2.0))
-            .with_translation(Vec3::new(0.0, 0.25, 0.01625));
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
+    use super::*;
+
+    fn bridge(max_length: Option<f32>, max_slope: Option<f32>) -> BuildingSegmentData {
+        BuildingSegmentData {
+            mesh_path: String::new(),
+            collider: Collider::cuboid(0.5, 0.5, 0.5),
+            offset: Vec3::ZERO,
+            end_offset: Vec3::ZERO,
+            cost_per_unit: 0,
+            max_length,
+            max_slope,
+        }
+    }
+
+    #[test]
+    fn span_limited_by_length() {
+        let segment = bridge(Some(10.0), None);
+
+        assert!(segment.span_allowed(10.0, Vec3::X));
+        assert!(!segment.span_allowed(10.5, Vec3::X));
+        // No slope limit
+        assert!(segment.span_allowed(5.0, Vec3::Y));
+    }
+
+    #[test]
+    fn span_limited_by_slope() {
+        let segment = bridge(None, Some(FRAC_PI_4));
+        let sloped = |degrees: f32| {
+            let angle = degrees.to_radians();
+            Vec3::new(angle.cos(), angle.sin(), 0.0)
+        };
+
+        assert!(segment.span_allowed(1000.0, sloped(44.0)));
+        assert!(!segment.span_allowed(1.0, sloped(46.0)));
+        // Going down is as steep as going up
+        assert!(!segment.span_allowed(1.0, sloped(-46.0)));
+    }
+
+    #[test]
+    fn unlimited_span() {
+        assert!(bridge(None, None).span_allowed(f32::MAX, Vec3::Y));
+    }
 }
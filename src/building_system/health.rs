@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+
+use crate::player_system::input::{Action, ActionInput};
+
+use super::{blueprint::BuildingCompletedEvent, raycasting::BuildCursor};
+
+/// Damage `Action::DamageBuilding` deals
+const DEBUG_DAMAGE: u32 = 10;
+
+/// The health of a building that has been fabricated
+#[derive(Component, Debug)]
+pub struct BuildingHealth {
+    pub current: u32,
+    pub max: u32,
+
+    /// Damage taken this frame that hasn't been applied yet, shields get the chance to absorb it first
+    pub pending: u32,
+}
+
+impl BuildingHealth {
+    pub fn new(max: u32) -> Self {
+        BuildingHealth {
+            current: max,
+            max,
+            pending: 0,
+        }
+    }
+}
+
+/// Send this to damage a building
+///
+/// `target` can be either the building itself or its collider. Only `debug_damage_input` sends it
+/// until weapons and enemies deal damage
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: u32,
+}
+
+/// Gives finished buildings their health
+pub fn init_building_health(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+
+    health_query: Query<(), With<BuildingHealth>>,
+) {
+    for event in completed_events.iter() {
+        // Loaded buildings already have the health they were saved with
+        if health_query.contains(event.entity) {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert(BuildingHealth::new(event.building.health));
+    }
+}
+
+/// Damages the building under the cursor when `Action::DamageBuilding` is pressed, to try out health
+/// and shields
+pub fn debug_damage_input(
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    if !input.just_pressed(Action::DamageBuilding) {
+        return;
+    }
+
+    if let Some((entity, _)) = build_cursor.intersection {
+        damage_events.send(DamageEvent {
+            target: entity,
+            amount: DEBUG_DAMAGE,
+        });
+    }
+}
+
+/// Adds the damage from every `DamageEvent` to the target's pending damage
+pub fn queue_damage(
+    mut damage_events: EventReader<DamageEvent>,
+
+    mut health_query: Query<&mut BuildingHealth>,
+    parent_query: Query<&Parent>,
+) {
+    for event in damage_events.iter() {
+        // The collider is a child of the actual building
+        let target = match health_query.contains(event.target) {
+            true => event.target,
+            false => match parent_query.get(event.target) {
+                Ok(e) => e.0,
+                Err(_) => continue,
+            },
+        };
+
+        if let Ok(mut health) = health_query.get_mut(target) {
+            health.pending = health.pending.saturating_add(event.amount);
+        }
+    }
+}
+
+/// Applies whatever pending damage is left and destroys buildings that run out of health
+pub fn apply_damage(
+    mut commands: Commands,
+    mut health_query: Query<(Entity, &mut BuildingHealth)>,
+) {
+    for (entity, mut health) in health_query.iter_mut() {
+        if health.pending == 0 {
+            continue;
+        }
+
+        health.current = health.current.saturating_sub(health.pending);
+        health.pending = 0;
+
+        if health.current == 0 {
+            info!("building {:?} was destroyed", entity);
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,188 @@
+use std::sync::Arc;
+
+use bevy::{gltf::GltfMesh, prelude::*};
+use bevy_rapier3d::prelude::Collider;
+
+use crate::iridium_system::storage::{IridiumGenerator, IridiumStorage};
+
+use super::{
+    building_components::PlacedBlueprint,
+    building_definitions::BuildingDefinition,
+    buildings::{
+        BuildingArcs, BuildingDefinitionIds, BuildingReferenceComponent, BuildingsResource,
+    },
+    health::BuildingHealth,
+    load_models::ModelManifest,
+};
+
+/// Applies edits to building definition files while the game runs (`GameSettings::watch_definitions`)
+///
+/// The edited building replaces the old one in `BuildingsResource`, `BuildingArcs` and the
+/// `BuildingReferenceComponent` of everything already placed, which also gets the new mesh, collider,
+/// health, storage capacity, generation and blueprint cost.
+///
+/// Files that don't parse or validate never get here, the loader logs why and the last good
+/// definition stays loaded. Edits that can't be applied while the game runs (changing `id`, a model
+/// that wasn't loaded at startup) are logged and the last good definition is kept too
+pub fn reload_building_definitions(
+    mut definition_events: EventReader<AssetEvent<BuildingDefinition>>,
+    definitions: Res<Assets<BuildingDefinition>>,
+    definition_ids: Res<BuildingDefinitionIds>,
+    model_manifest: Res<ModelManifest>,
+    mut buildings: ResMut<BuildingsResource>,
+    mut building_arcs: ResMut<BuildingArcs>,
+
+    mut building_query: Query<(
+        &mut BuildingReferenceComponent,
+        Option<&Children>,
+        Option<&mut Handle<Mesh>>,
+        Option<&mut Handle<StandardMaterial>>,
+        Option<&mut PlacedBlueprint>,
+        Option<&mut BuildingHealth>,
+        Option<&mut IridiumStorage>,
+        Option<&mut IridiumGenerator>,
+    )>,
+    mut collider_query: Query<(&mut Collider, &mut Transform)>,
+
+    asset_server: Res<AssetServer>,
+    gltf_meshes: ResMut<Assets<GltfMesh>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for event in definition_events.iter() {
+        let handle = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+        let definition = match definitions.get(handle) {
+            Some(e) => e,
+            None => continue,
+        };
+        let path = asset_server.get_handle_path(handle);
+
+        let id = match definition_ids.0.get(&handle.id) {
+            Some(e) => e,
+            None => {
+                warn!(
+                    "{:?}: wasn't loaded when the game started, restart it to add the building",
+                    path
+                );
+                continue;
+            }
+        };
+        if definition.id != *id {
+            error!(
+                "{:?}: field `id`: can't be changed while the game runs, keeping the last good definition of {:?}",
+                path, id
+            );
+            continue;
+        }
+        let old = match buildings.0.get(id) {
+            Some(e) => e.clone(),
+            None => continue,
+        };
+
+        let mut building = definition.to_building();
+        if building.shape_data.path == old.shape_data.path {
+            building.shape_data.mesh = old.shape_data.mesh.clone();
+            building.shape_data.material = old.shape_data.material.clone();
+        } else if model_manifest.is_loaded(&building.shape_data.path) {
+            building.shape_data.load_from_path(
+                &asset_server,
+                &gltf_meshes,
+                &mut meshes,
+                &mut materials,
+                &mut images,
+            );
+        } else {
+            error!(
+                "{:?}: field `mesh_path`: {:?} wasn't loaded when the game started, keeping the last good definition",
+                path, definition.mesh_path
+            );
+            continue;
+        }
+
+        let enabled = |e: Option<u32>| e.filter(|e| *e > 0).is_some();
+        if enabled(old.iridium_data.storage) != enabled(building.iridium_data.storage)
+            || enabled(old.iridium_data.generation) != enabled(building.iridium_data.generation)
+        {
+            warn!(
+                "{:?}: placed buildings keep having (or not having) storage and generation, only new ones are affected",
+                path
+            );
+        }
+
+        buildings.0.insert(id.clone(), building.clone());
+        let building = Arc::new(building);
+        building_arcs.0.insert(id.clone(), building.clone());
+
+        let mut refreshed = 0;
+        for (mut building_ref, children, mesh, material, blueprint, health, storage, generator) in
+            building_query.iter_mut()
+        {
+            if building_ref.0.building_id.building_type != *id {
+                continue;
+            }
+            building_ref.0 = building.clone();
+            refreshed += 1;
+
+            // Blueprints and previews have their own materials, only the ones showing the building change
+            if let (Some(mut mesh), Some(new)) = (mesh, &building.shape_data.mesh) {
+                if old.shape_data.mesh.as_ref() == Some(&*mesh) {
+                    *mesh = new.clone();
+                }
+            }
+            if let (Some(mut material), Some(new)) = (material, &building.shape_data.material) {
+                if old.shape_data.material.as_ref() == Some(&*material) {
+                    *material = new.clone();
+                }
+            }
+
+            // The collider is the first child, it's moved by the difference between both offsets
+            if let Some((mut collider, mut transform)) =
+                children.and_then(|e| collider_query.get_mut(e[0]).ok())
+            {
+                *collider = building.shape_data.collider.clone();
+                let offset = building.shape_data.collider_offset - old.shape_data.collider_offset;
+                let offset = transform.rotation.mul_vec3(offset);
+                transform.translation += offset;
+            }
+
+            // Blueprints that already have enough get finished by `apply_blueprint_progress`
+            if let Some(mut blueprint) = blueprint {
+                if blueprint.cost != building.iridium_data.cost {
+                    blueprint.cost = building.iridium_data.cost;
+                }
+            }
+
+            if let Some(mut health) = health {
+                health.current = if building.health > health.max {
+                    health.current + (building.health - health.max)
+                } else {
+                    health.current.min(building.health)
+                };
+                health.max = building.health;
+            }
+
+            if let (Some(mut storage), Some(capacity)) =
+                (storage, building.iridium_data.storage.filter(|e| *e > 0))
+            {
+                storage.capacity = capacity;
+                storage.current = storage.current.min(capacity);
+            }
+
+            if let (Some(mut generator), Some(generation)) = (
+                generator,
+                building.iridium_data.generation.filter(|e| *e > 0),
+            ) {
+                generator.0 = generation;
+            }
+        }
+
+        info!(
+            "{:?}: reloaded {:?}, {} placed buildings refreshed",
+            path, id, refreshed
+        );
+    }
+}
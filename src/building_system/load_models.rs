@@ -1,39 +1,67 @@
-use bevy::{asset::LoadState, ecs::schedule::ShouldRun, gltf::GltfMesh, prelude::*};
-
-use super::ModelHandles;
-
-pub const NONE_HANDLE: Option<Handle<GltfMesh>> = None;
-pub const NUM_MODELS: usize = 7;
-
-const MODEL_PATHS: [&'static str; NUM_MODELS] = [
-    "models/buildings/well_pump.gltf",
-    "models/pipes/pipe_base.gltf",
-    "models/ground1/ground1.gltf",
-    "models/ground1/hollow_ground.gltf",
-    "models/ground1/spires_full.gltf",
-    "models/ground1/spires_hollow.gltf",
-    "models/ground1/well_ground.gltf",
-];
-
-pub fn initiate_load(asset_server: Res<AssetServer>, mut model_handles: ResMut<ModelHandles>) {
-    info!("load start");
-    for (i, path) in MODEL_PATHS.iter().enumerate() {
-        let e = Some(asset_server.load(&format!("{}{}", path, "#Mesh0")));
-        model_handles.handles[i] = e;
+use bevy::{asset::LoadState, gltf::GltfMesh, prelude::*};
+
+struct ManifestEntry {
+    path: String,
+    handle: Handle<GltfMesh>,
+    state: LoadState,
+}
+
+/// Every model that has to be loaded before buildings and terrain can be set up
+///
+/// Filled from the building definitions and terrain blocks, models only have to be listed there
+#[derive(Default)]
+pub struct ModelManifest {
+    models: Vec<ManifestEntry>,
+}
+
+impl ModelManifest {
+    /// Starts loading the first mesh of the GLTF at `path`, does nothing if it's already in the manifest
+    pub fn request(&mut self, path: &str, asset_server: &AssetServer) {
+        if self.contains(path) {
+            return;
+        }
+
+        self.models.push(ManifestEntry {
+            path: path.to_string(),
+            handle: asset_server.load(&format!("{}{}", path, "#Mesh0")),
+            state: LoadState::NotLoaded,
+        });
     }
-    info!("load done");
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.models.iter().any(|e| e.path == path)
+    }
+
+    pub fn is_loaded(&self, path: &str) -> bool {
+        self.models
+            .iter()
+            .any(|e| e.path == path && e.state == LoadState::Loaded)
+    }
+
+    /// `true` once every model in the manifest either loaded or failed
+    pub fn is_done(&self) -> bool {
+        self.models.iter().all(|e| is_finished(e.state))
+    }
+}
+
+fn is_finished(state: LoadState) -> bool {
+    matches!(state, LoadState::Loaded | LoadState::Failed)
 }
 
-pub fn get_load_states(
-    asset_server: Res<AssetServer>,
-    model_handles: Res<ModelHandles>,
-) -> ShouldRun {
-    for handle in model_handles.handles.clone() {
-        match asset_server.get_load_state(handle.as_ref().unwrap()) {
-            LoadState::Loaded => continue,
-            LoadState::Failed => warn!("Failed to load model, Handle: {:?}", handle.unwrap()),
-            _ => return ShouldRun::NoAndCheckAgain,
+/// Keeps the load state of every model up to date, naming the ones that fail
+pub fn track_model_loads(asset_server: Res<AssetServer>, mut manifest: ResMut<ModelManifest>) {
+    if manifest.is_done() {
+        return;
+    }
+
+    for entry in manifest.models.iter_mut() {
+        if is_finished(entry.state) {
+            continue;
+        }
+
+        entry.state = asset_server.get_load_state(&entry.handle);
+        if entry.state == LoadState::Failed {
+            error!("Failed to load model {:?}", entry.path);
         }
     }
-    return ShouldRun::Yes;
 }
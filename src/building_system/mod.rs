@@ -2,25 +2,30 @@ use crate::building_system::building::building;
 use crate::building_system::snapping::snapping;
 use bevy::{
     core::FixedTimestep,
-    gltf::GltfMesh,
     pbr::{AlphaMode, PbrBundle, StandardMaterial},
     prelude::{
-        default, info, shape, Assets, Color, Commands, CoreStage, Handle, Mesh,
+        default, info, shape, AddAsset, Assets, Color, Commands, CoreStage, Handle, Mesh,
         ParallelSystemDescriptorCoercion, Plugin, ResMut, SystemSet, Transform,
     },
 };
 use bevy_mod_raycast::RayCastMesh;
 
-use crate::player_system::player::player_camera_system;
+use crate::{player_system::player::player_camera_system, settings::GameSettings};
 
 use self::{
-    blueprint::update_blueprints,
+    blueprint::{
+        apply_blueprint_progress, apply_segment_progress, update_blueprints,
+        BuildingCompletedEvent,
+    },
     building_components::*,
+    building_definitions::{load_building_definitions, BuildingDefinition, BuildingDefinitionLoader},
     buildings::{
         building_init_done, building_init_not_done_and_get_load_states, load_buildings_in_resource,
-        load_buildings_into_resource, BuildingInitDone,
+        request_building_models, BuildingInitDone,
     },
-    load_models::{initiate_load, NONE_HANDLE, NUM_MODELS},
+    health::{apply_damage, debug_damage_input, init_building_health, queue_damage, DamageEvent},
+    hot_reload::reload_building_definitions,
+    load_models::{track_model_loads, ModelManifest},
     placement::check_cursor_bp_collision,
     raycasting::{raycast, BuildCursor, LatestCursorPosition, RaycastCursor},
 };
@@ -28,8 +33,11 @@ use self::{
 pub mod blueprint;
 pub mod building;
 pub mod building_components;
+pub mod building_definitions;
 pub mod building_functions;
 pub mod buildings;
+pub mod health;
+pub mod hot_reload;
 pub mod load_models;
 pub mod placement;
 pub mod raycasting;
@@ -37,14 +45,11 @@ pub mod snapping;
 
 pub struct RaycastSet;
 
-pub struct ModelHandles {
-    handles: [Option<Handle<GltfMesh>>; NUM_MODELS],
-}
-
 #[derive(Clone)]
 pub struct MaterialHandles {
     blueprint: Handle<StandardMaterial>,
     obstructed: Handle<StandardMaterial>,
+    out_of_territory: Handle<StandardMaterial>,
 }
 
 pub struct BlueprintFillMaterial(Vec<Handle<StandardMaterial>>);
@@ -57,22 +62,40 @@ pub struct PipeCylinderMaterial(pub Handle<StandardMaterial>);
 
 impl Plugin for BuildingSystemPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.insert_resource(ModelHandles {
-            handles: [NONE_HANDLE; NUM_MODELS],
-        })
+        let blueprint_steps_per_second = app
+            .world
+            .get_resource::<GameSettings>()
+            .expect("GameSettings has to be inserted before BuildingSystemPlugin")
+            .blueprint_steps_per_second;
+
+        app.init_resource::<ModelManifest>()
         .insert_resource(ChangeBuilding { b: false })
         .insert_resource(BuildingInitDone(false))
         .insert_resource(GlobalPipeId(0))
         .insert_resource(LatestCursorPosition(None))
+        .add_asset::<BuildingDefinition>()
+        .init_asset_loader::<BuildingDefinitionLoader>()
+        .add_event::<BuildingCompletedEvent>()
+        .add_event::<DamageEvent>()
         .add_startup_system(building_system_startup)
-        .add_startup_system(initiate_load)
-        .add_startup_system(load_buildings_into_resource)
+        .add_startup_system(load_building_definitions)
+        .add_system_to_stage(CoreStage::PreUpdate, request_building_models)
+        .add_system_to_stage(
+            CoreStage::PreUpdate,
+            track_model_loads.after(request_building_models),
+        )
         .add_system_set_to_stage(
             CoreStage::PreUpdate,
             SystemSet::new()
                 .with_run_criteria(building_init_not_done_and_get_load_states)
                 .with_system(load_buildings_in_resource),
         )
+        .add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::new()
+                .with_run_criteria(building_init_done)
+                .with_system(reload_building_definitions),
+        )
         .add_system_set_to_stage(
             CoreStage::PostUpdate,
             SystemSet::new()
@@ -84,9 +107,17 @@ impl Plugin for BuildingSystemPlugin {
         )
         .add_system_set(
             SystemSet::new()
-                .with_run_criteria(FixedTimestep::steps_per_second(30.0))
+                .with_run_criteria(FixedTimestep::steps_per_second(f64::from(
+                    blueprint_steps_per_second,
+                )))
                 .with_system(update_blueprints),
         )
+        .add_system(apply_blueprint_progress)
+        .add_system(apply_segment_progress)
+        .add_system(init_building_health)
+        .add_system(debug_damage_input.before(queue_damage))
+        .add_system(queue_damage)
+        .add_system(apply_damage.after(queue_damage))
         .add_system(player_camera_system);
     }
 }
@@ -178,6 +209,21 @@ impl MaterialHandles {
                 alpha_mode: AlphaMode::Blend,
                 ..Default::default()
             }),
+            out_of_territory: materials.add(StandardMaterial {
+                base_color: Color::rgba(1.0, 0.6, 0.0, 0.5),
+                alpha_mode: AlphaMode::Blend,
+                ..Default::default()
+            }),
+        }
+    }
+
+    pub fn for_obstruction(&self, obstruction: Obstruction) -> Handle<StandardMaterial> {
+        match obstruction {
+            Obstruction::None => self.blueprint.clone(),
+            Obstruction::OutOfTerritory => self.out_of_territory.clone(),
+            Obstruction::Colliding | Obstruction::Limit | Obstruction::InvalidSpan => {
+                self.obstructed.clone()
+            }
         }
     }
 }
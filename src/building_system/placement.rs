@@ -5,17 +5,17 @@ use bevy_mod_raycast::{RayCastMesh, SimplifiedMesh};
 use bevy_rapier3d::prelude::*;
 
 use crate::{
-    constants::{BLUEPRINT_COLLISION, PIPE_CYLINDER_OFFSET},
+    collision_layers::{CollisionLayer, CollisionLayers},
+    constants::BLUEPRINT_COLLISION,
     player_system::gui_system::gui_startup::SelectedBuilding,
+    settings::GameSettings,
+    structure_system::keep::{territory_obstruction, Keep},
 };
 
 use super::{
-    building::EntityQuery,
-    building_components::*,
-    building_functions::MoveTransform,
-    buildings::{BuildingReferenceComponent, BuildingType},
-    raycasting::BuildCursor,
-    BlueprintFillMaterial, GlobalPipeId, MaterialHandles, RaycastSet,
+    building::{EntityQuery, Pipe}, building_components::*, building_functions::MoveTransform,
+    buildings::BuildingReferenceComponent, raycasting::BuildCursor, BlueprintFillMaterial,
+    GlobalPipeId, MaterialHandles, RaycastSet,
 };
 
 pub fn check_cursor_bp_collision(
@@ -23,11 +23,12 @@ pub fn check_cursor_bp_collision(
 
     cursor_bp: EntityQuery<CursorBp>,
     cursor_bp_collider: EntityQuery<CursorBpCollider>,
-    pipe_preview_cylinder: EntityQuery<PipePreviewCylinder>,
-    pipe_placement: EntityQuery<PipePreviewPlacement>,
-    pipe_preview: EntityQuery<PipePreview>,
+    segment_preview_piece: EntityQuery<SegmentPreviewPiece>,
+    segment_placement: EntityQuery<SegmentPreviewPlacement>,
+    segment_preview: EntityQuery<SegmentPreview>,
 
     rapier_context: Res<RapierContext>,
+    settings: Res<GameSettings>,
     bp_material_handles: Res<MaterialHandles>,
     bp_fill_materials: Res<BlueprintFillMaterial>,
     mut selected_building: ResMut<SelectedBuilding>,
@@ -45,6 +46,8 @@ pub fn check_cursor_bp_collision(
         building_ref_query,
         try_place_query,
         placeable_query,
+        snapped_query,
+        keep_query,
     ): (
         Query<&mut Moved>,
         Query<&Children>,
@@ -54,8 +57,12 @@ pub fn check_cursor_bp_collision(
         Query<&BuildingReferenceComponent>,
         Query<&TryPlace>,
         Query<&Placeable>,
+        Query<&SnappedTo>,
+        Query<&Transform, With<Keep>>,
     ),
 ) {
+    let keeps: Vec<Vec3> = keep_query.iter().map(|e| e.translation).collect();
+
     for (cbp_entity, cbp_collider_entity) in cursor_bp.iter().zip(cursor_bp_collider.iter()) {
         let mut moved = moved_query.get_mut(cbp_collider_entity).unwrap();
         let try_place = try_place_query.contains(cbp_entity);
@@ -65,20 +72,27 @@ pub fn check_cursor_bp_collision(
             .get(cbp_entity)
             .unwrap_or(&Placeable::WithCollision);
 
-        let can_place = match placeable {
-            Placeable::Yes => true,
-            Placeable::WithCollision => !intersecting,
-            Placeable::No => false,
+        let obstruction = match placeable {
+            Placeable::Yes => Obstruction::None,
+            Placeable::WithCollision if !intersecting => Obstruction::None,
+            _ => Obstruction::Colliding,
+        };
+
+        // Only check the territory if nothing else is in the way
+        let obstruction = match obstruction {
+            Obstruction::None => territory_obstruction(
+                &building_ref_query.get(cbp_entity).unwrap().0.building_id.building_type,
+                transform_query.get(cbp_entity).unwrap().translation,
+                settings.keep_build_radius,
+                &keeps,
+            ),
+            e => e,
         };
+        let can_place = obstruction == Obstruction::None;
 
         if moved.0 {
             let mut mat = material_query.get_mut(cbp_entity).unwrap();
-
-            if can_place {
-                *mat = bp_material_handles.blueprint.clone();
-            } else {
-                *mat = bp_material_handles.obstructed.clone();
-            }
+            *mat = bp_material_handles.for_obstruction(obstruction);
 
             moved.0 = false;
         }
@@ -90,61 +104,67 @@ pub fn check_cursor_bp_collision(
             if can_place {
                 let building = &building_ref_query.get(cbp_entity).unwrap().0;
 
-                match building.building_id.building_type {
-                    BuildingType::Pipe => {
-                        if pipe_preview.is_empty() {
-                            let pipe_cyl_mesh: Handle<Mesh> =
-                                asset_server.load("models/pipes/pipe_cylinder.obj");
+                match &building.segment_data {
+                    Some(segment) => {
+                        if segment_preview.is_empty() {
+                            let piece_mesh: Handle<Mesh> = asset_server.load(&segment.mesh_path);
 
                             let rot = bc_res.rotation;
                             let transform = transform_query.get(cbp_entity).unwrap();
-                            let offset_transform =
-                                transform.with_add_translation(*PIPE_CYLINDER_OFFSET);
+                            let offset_transform = transform.with_add_translation(segment.offset);
+
+                            let snapped_to = snapped_query.get(cbp_entity).ok().copied();
 
                             commands
                                 .spawn()
                                 .insert_bundle((
                                     GlobalTransform::identity(),
                                     Transform::default(),
-                                    PipePreview,
+                                    SegmentPreview,
                                     BuildingReferenceComponent(building.clone()),
                                 ))
                                 .with_children(|parent| {
                                     parent
                                         .spawn_bundle(PbrBundle {
-                                            mesh: pipe_cyl_mesh,
+                                            mesh: piece_mesh,
                                             material: bp_material_handles.blueprint.clone(),
                                             transform: offset_transform
                                                 .with_scale(Vec3::new(1.0, 0.001, 1.0)),
                                             ..Default::default()
                                         })
-                                        .insert(PipePreviewCylinder)
+                                        .insert(SegmentPreviewPiece)
                                         .with_children(|parent| {
                                             parent.spawn_bundle((
                                                 offset_transform
                                                     .with_scale(Vec3::new(1.0, 0.001, 1.0)),
-                                                Collider::cuboid(0.135, 0.5, 0.135),
-                                                CollisionGroups {
-                                                    memberships: 0b00001000,
-                                                    filters: 0b11101111,
-                                                },
+                                                segment.collider.clone(),
+                                                CollisionLayers::of(CollisionLayer::SegmentPreview)
+                                                    .groups(),
                                                 Sensor(true),
-                                                PipePreviewCylinderCollider,
+                                                SegmentPreviewPieceCollider,
                                                 NotShadowCaster,
                                             ));
                                         });
 
-                                    parent
-                                        .spawn_bundle(PbrBundle {
-                                            mesh: building.shape_data.mesh.clone().unwrap(),
-                                            material: bp_material_handles.blueprint.clone(),
-                                            transform: *transform,
-                                            ..Default::default()
-                                        })
+                                    let mut first_placement = parent.spawn_bundle(PbrBundle {
+                                        mesh: building.shape_data.mesh.clone().unwrap(),
+                                        material: bp_material_handles.blueprint.clone(),
+                                        transform: *transform,
+                                        ..Default::default()
+                                    });
+
+                                    // Keep track of what the first end was snapped to for the iridium network
+                                    if let Some(snapped_to) = snapped_to {
+                                        first_placement.insert(snapped_to);
+                                    }
+
+                                    // The ends keep a reference too so other buildings can snap onto them (gates onto walls)
+                                    first_placement
                                         .insert_bundle((
-                                            PipePreviewPlacement,
+                                            SegmentPreviewPlacement,
                                             NotShadowCaster,
                                             Placeable::Yes,
+                                            BuildingReferenceComponent(building.clone()),
                                         ))
                                         .with_children(|parent| {
                                             parent.spawn_bundle((
@@ -152,6 +172,10 @@ pub fn check_cursor_bp_collision(
                                                 transform.with_add_translation(
                                                     building.shape_data.collider_offset,
                                                 ),
+                                                // Same groups as the cursor, so the piece
+                                                // starting inside of it doesn't count as obstructed
+                                                CollisionLayers::of(CollisionLayer::Cursor)
+                                                    .groups(),
                                                 Sensor(true),
                                                 BuildingRotation(rot),
                                             ));
@@ -162,7 +186,7 @@ pub fn check_cursor_bp_collision(
                             bc_res.rotation += PI;
                         }
                     }
-                    _ => {
+                    None => {
                         commands
                             .entity(cbp_collider_entity)
                             .remove_bundle::<(Moved, CursorBpCollider)>()
@@ -182,14 +206,14 @@ pub fn check_cursor_bp_collision(
         }
     }
 
-    if !pipe_preview.is_empty() {
-        let pipe_cylinder = pipe_preview_cylinder.single();
-        let first = pipe_placement.single();
+    if !segment_preview.is_empty() {
+        let piece = segment_preview_piece.single();
+        let first = segment_placement.single();
         let second = cursor_bp.single();
 
-        let mut intersecting = false;
+        let mut obstruction = Obstruction::None;
 
-        for entity in [pipe_cylinder, first, second] {
+        for entity in [piece, first, second] {
             if match placeable_query
                 .get(entity)
                 .unwrap_or(&Placeable::WithCollision)
@@ -200,68 +224,106 @@ pub fn check_cursor_bp_collision(
                 }
                 Placeable::No => true,
             } {
-                intersecting = true;
+                obstruction = Obstruction::Colliding;
                 break;
             }
         }
 
-        let set_material = match intersecting {
-            true => bp_material_handles.obstructed.clone(),
-            false => bp_material_handles.blueprint.clone(),
-        };
+        let building_ref = &building_ref_query.get(segment_preview.single()).unwrap().0;
+
+        // Too long or too steep (bridges)
+        if obstruction == Obstruction::None {
+            if let Some(segment) = &building_ref.segment_data {
+                let piece_transform = transform_query.get(piece).unwrap();
+
+                if !segment.span_allowed(
+                    piece_transform.scale.y,
+                    piece_transform.rotation.mul_vec3(Vec3::Y),
+                ) {
+                    obstruction = Obstruction::InvalidSpan;
+                }
+            }
+        }
+
+        // Both ends have to be in the territory
+        if obstruction == Obstruction::None {
+            obstruction = [first, second]
+                .iter()
+                .map(|e| {
+                    territory_obstruction(
+                        &building_ref.building_id.building_type,
+                        transform_query.get(*e).unwrap().translation,
+                        settings.keep_build_radius,
+                        &keeps,
+                    )
+                })
+                .find(|e| *e != Obstruction::None)
+                .unwrap_or(Obstruction::None);
+        }
+
+        let set_material = bp_material_handles.for_obstruction(obstruction);
 
         material_query
-            .get_many_mut([pipe_cylinder, first, second])
+            .get_many_mut([piece, first, second])
             .unwrap()
             .map(|mut material| *material = set_material.clone());
 
-        let pipe_preview_entity = pipe_preview.single();
+        let segment_preview_entity = segment_preview.single();
 
-        if try_place_query.contains(pipe_preview_entity) && !intersecting {
+        if try_place_query.contains(segment_preview_entity) && obstruction == Obstruction::None {
             let place_mat = bp_fill_materials.get_fill_percent(0.0);
-            let building_ref = &building_ref_query.get(pipe_preview_entity).unwrap().0;
+            let building_ref = &building_ref_query.get(segment_preview_entity).unwrap().0;
+
+            // The piece is scaled to the distance between both ends
+            let length = transform_query.get(piece).unwrap().scale.y;
 
             commands
-                .entity(pipe_preview_entity)
-                .remove::<PipePreview>()
-                .insert(PipeBlueprint {
-                    cost: building_ref.iridium_data.cost,
-                    current: 0,
-                });
-
-            // First base of the pipe
+                .entity(segment_preview_entity)
+                .remove::<SegmentPreview>()
+                .insert_bundle((
+                    SegmentBlueprint {
+                        cost: building_ref.cost_with_length(length),
+                        current: 0,
+                    },
+                    Pipe::new(
+                        *transform_query.get(first).unwrap(),
+                        *transform_query.get(second).unwrap(),
+                    ),
+                ));
+
+            // First end
             commands
                 .entity(first)
-                .remove::<PipePreviewPlacement>()
-                .insert_bundle((PipeFirst, place_mat.clone()));
+                .remove::<SegmentPreviewPlacement>()
+                .insert_bundle((SegmentFirst, place_mat.clone()));
 
-            // First base of the pipe's collider
+            // First end's collider
             commands
                 .entity(children_query.get(first).unwrap()[0])
                 .insert_bundle((BLUEPRINT_COLLISION.clone(), Sensor(false)));
 
-            // Second base of the pipe
+            // Second end
             commands
                 .entity(second)
                 .remove::<CursorBp>()
-                .insert_bundle((PipeSecond, place_mat.clone()));
+                .insert_bundle((SegmentSecond, place_mat.clone()));
 
-            // Second base of the pipe's collider
+            // Second end's collider
             commands
                 .entity(children_query.get(second).unwrap()[0])
                 .remove::<CursorBpCollider>()
                 .insert_bundle((BLUEPRINT_COLLISION.clone(), Sensor(false)));
 
-            // Pipe cylinder in between both
+            // Piece in between both
             commands
-                .entity(pipe_cylinder)
-                .remove::<PipePreviewCylinder>()
-                .insert_bundle((PipeCylinder, place_mat.clone()));
+                .entity(piece)
+                .remove::<SegmentPreviewPiece>()
+                .insert_bundle((SegmentPiece, place_mat.clone()));
 
-            // Pipe cylinder collider
+            // Piece collider
             commands
-                .entity(children_query.get(pipe_cylinder).unwrap()[0])
-                .remove::<PipePreviewCylinderCollider>()
+                .entity(children_query.get(piece).unwrap()[0])
+                .remove::<SegmentPreviewPieceCollider>()
                 .insert_bundle((BLUEPRINT_COLLISION.clone(), Sensor(false)));
 
             selected_building.id = None;
@@ -269,8 +331,8 @@ pub fn check_cursor_bp_collision(
             global_pipe_id.0 += 1;
         }
 
-        if try_place_query.contains(pipe_preview_entity) {
-            commands.entity(pipe_preview_entity).remove::<TryPlace>();
+        if try_place_query.contains(segment_preview_entity) {
+            commands.entity(segment_preview_entity).remove::<TryPlace>();
         }
     }
 }
@@ -1,8 +1,9 @@
 use bevy::prelude::*;
 use bevy_mod_raycast::Ray3d;
-use bevy_rapier3d::{plugin::RapierContext, prelude::{InteractionGroups, RayIntersection}, math::Real};
+use bevy_rapier3d::{plugin::RapierContext, prelude::RayIntersection, math::Real};
 
 
+use crate::collision_layers::{CollisionLayer, CollisionLayers};
 use crate::player_system::player::CameraComp;
 
 #[derive(Component)]
@@ -16,6 +17,14 @@ pub struct BuildCursor {
     pub rotation: f32,
 }
 
+impl BuildCursor {
+    /// The entity the cursor is over, colliders are resolved to the building they're a child of
+    pub fn hovered_entity(&self, parent_query: &Query<&Parent>) -> Option<Entity> {
+        let (entity, _) = self.intersection?;
+        Some(parent_query.get(entity).map_or(entity, |e| e.0))
+    }
+}
+
 pub struct LatestCursorPosition(pub Option<Vec2>);
 
 pub fn raycast(
@@ -51,10 +60,7 @@ pub fn raycast(
         ray.direction(), 
         Real::MAX, 
         true, 
-        InteractionGroups {
-            memberships: 0b111111000,
-            filter: 0b1111100101,
-        }, 
+        CollisionLayers::of(CollisionLayer::Raycast).interaction_groups(), 
         None
     );
 
@@ -4,9 +4,9 @@ use bevy::prelude::*;
 
 use crate::{
     algorithms::{distance_vec3, ChildrenMethods},
-    constants::SNAP_DISTANCE,
     player_system::gui_system::gui_startup::SelectedBuilding,
-    terrain_generation_system::terrain_block::TerrainBlockName,
+    settings::GameSettings,
+    terrain_generation_system::terrain_block::{Blocks, TerrainBlockName, WELL_TAG},
 };
 
 use super::{
@@ -22,6 +22,8 @@ pub fn snapping(
 
     build_cursor: Res<BuildCursor>,
     selected_building: Res<SelectedBuilding>,
+    terrain_blocks: Option<Res<Blocks>>,
+    settings: Res<GameSettings>,
 
     (cbp_entity_query, cbp_collider_entity_query): (
         EntityQuery<CursorBp>,
@@ -70,6 +72,9 @@ pub fn snapping(
     let cbp_entity = cbp_entity_query.single();
     let cbp_collider_entity = cbp_collider_entity_query.single();
 
+    // Re-added below if the cursor blueprint is still snapped to something this frame
+    commands.entity(cbp_entity).remove::<SnappedTo>();
+
     let [mut cursor_bp_transform, mut cursor_bp_collider_transform, relative_transform] =
         match transform_query.get_many_mut([cbp_entity, cbp_collider_entity, intersected_entity]) {
             Ok(e) => e,
@@ -85,10 +90,15 @@ pub fn snapping(
         BuildingType::Wellpump => {
             commands.entity(cbp_entity).insert(Placeable::No);
 
-            if let Ok(TerrainBlockName("well_ground")) = well_query.get(intersected_entity) {
+            let on_well = match (well_query.get(intersected_entity), &terrain_blocks) {
+                (Ok(name), Some(blocks)) => blocks.has_tag(&name.0, WELL_TAG),
+                _ => false,
+            };
+
+            if on_well {
                 let goal_translation = relative_transform.translation.add(Vec3::new(0.0, 1.5, 0.0));
 
-                if distance_vec3(goal_translation, intersection.point) <= SNAP_DISTANCE {
+                if distance_vec3(goal_translation, intersection.point) <= settings.snap_distance {
                     let goal_transform = Transform::from_translation(goal_translation)
                         .with_rotation(Quat::from_axis_angle(Vec3::Y, rot));
 
@@ -130,7 +140,7 @@ pub fn snapping(
                                     relative_transform.with_add_translation(*trans).translation;
 
                                 if distance_vec3(snap_translation, intersection.point)
-                                    <= SNAP_DISTANCE
+                                    <= settings.snap_distance
                                 {
                                     let rot_range = can_rotate.clone().add(
                                         building_rot_query
@@ -161,6 +171,10 @@ pub fn snapping(
                                         &mut building_rot,
                                     );
 
+                                    commands
+                                        .entity(cbp_entity)
+                                        .insert(SnappedTo(intersected_entity));
+
                                     break;
                                 }
                             }
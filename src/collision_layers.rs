@@ -0,0 +1,240 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{CollisionGroups, InteractionGroups};
+
+/// Every kind of collider, each one is a bit of `CollisionGroups`
+///
+/// Add new kinds here instead of writing bitmasks, `CollisionLayer::interacts_with` decides what they
+/// collide with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollisionLayer {
+    Terrain,
+    /// Players and vehicles
+    Player,
+    /// Finished buildings are in every layer, this is the one queries use to find only them
+    Building,
+    /// Segment preview pieces and spatial anchor arrival sensors
+    SegmentPreview,
+    Cursor,
+    Blueprint,
+    /// Rays cast by the build cursor
+    Raycast,
+    ShieldDome,
+    /// Rays drones steer with, the only thing finished buildings don't collide with
+    DroneRay,
+}
+
+impl CollisionLayer {
+    pub const ALL: [CollisionLayer; 9] = [
+        CollisionLayer::Terrain,
+        CollisionLayer::Player,
+        CollisionLayer::Building,
+        CollisionLayer::SegmentPreview,
+        CollisionLayer::Cursor,
+        CollisionLayer::Blueprint,
+        CollisionLayer::Raycast,
+        CollisionLayer::ShieldDome,
+        CollisionLayer::DroneRay,
+    ];
+
+    pub const fn bit(self) -> u32 {
+        1 << self as u32
+    }
+
+    /// What the colliders in this layer are actually in and collide with
+    pub const fn colliders(self) -> CollisionLayers {
+        match self {
+            CollisionLayer::Building => CollisionLayers::FINISHED_BUILDING,
+            e => CollisionLayers::of(e),
+        }
+    }
+
+    /// The layers this one collides with, has to go both ways (`check_collision_layers` complains if
+    /// it doesn't)
+    pub const fn interacts_with(self) -> Layers {
+        use CollisionLayer::*;
+
+        match self {
+            Terrain => Layers::of(&[
+                Player,
+                Building,
+                SegmentPreview,
+                Cursor,
+                Blueprint,
+                Raycast,
+                DroneRay,
+            ]),
+            Player => Layers::of(&[Terrain, Player, Building, SegmentPreview]),
+            Building => Layers::ALL.without(DroneRay),
+            SegmentPreview => Layers::of(&[Terrain, Player, Building, SegmentPreview, Blueprint]),
+            Cursor => Layers::of(&[Terrain, Building, Cursor, Blueprint]),
+            Blueprint => Layers::of(&[
+                Terrain,
+                Building,
+                SegmentPreview,
+                Cursor,
+                Blueprint,
+                Raycast,
+            ]),
+            Raycast => Layers::of(&[Terrain, Building, Blueprint]),
+            ShieldDome => Layers::of(&[Building]),
+            DroneRay => Layers::of(&[Terrain]),
+        }
+    }
+}
+
+/// A set of collision layers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Layers(pub u32);
+
+impl Layers {
+    pub const NONE: Layers = Layers(0);
+    pub const ALL: Layers = Layers(u32::MAX);
+
+    pub const fn of(layers: &[CollisionLayer]) -> Self {
+        let mut bits = 0;
+        let mut i = 0;
+        while i < layers.len() {
+            bits |= layers[i].bit();
+            i += 1;
+        }
+        Layers(bits)
+    }
+
+    pub const fn with(self, layer: CollisionLayer) -> Self {
+        Layers(self.0 | layer.bit())
+    }
+
+    pub const fn without(self, layer: CollisionLayer) -> Self {
+        Layers(self.0 & !layer.bit())
+    }
+
+    pub const fn contains(self, layer: CollisionLayer) -> bool {
+        self.0 & layer.bit() != 0
+    }
+}
+
+/// Which layers a collider (or query) is in and which ones it collides with
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    pub memberships: Layers,
+    pub filters: Layers,
+}
+
+impl CollisionLayers {
+    pub const NONE: CollisionLayers = CollisionLayers::new(Layers::NONE, Layers::NONE);
+
+    /// Finished buildings (and their sensors) are in every layer and collide with everything but drone
+    /// rays, so anything else that collides with any layer collides with them
+    pub const FINISHED_BUILDING: CollisionLayers =
+        CollisionLayers::new(Layers::ALL, CollisionLayer::Building.interacts_with());
+
+    /// Rays looking for what a building was placed on, they hit whatever a player would stand on
+    pub const MOUNT_RAY: CollisionLayers = CollisionLayers::of(CollisionLayer::Player);
+
+    pub const fn new(memberships: Layers, filters: Layers) -> Self {
+        CollisionLayers {
+            memberships,
+            filters,
+        }
+    }
+
+    /// In `layer` and colliding with everything it interacts with
+    pub const fn of(layer: CollisionLayer) -> Self {
+        CollisionLayers::new(Layers::of(&[layer]), layer.interacts_with())
+    }
+
+    /// Also in `layer`
+    pub const fn member_of(self, layer: CollisionLayer) -> Self {
+        CollisionLayers::new(self.memberships.with(layer), self.filters)
+    }
+
+    /// Also colliding with `layer`
+    pub const fn colliding_with(self, layer: CollisionLayer) -> Self {
+        CollisionLayers::new(self.memberships, self.filters.with(layer))
+    }
+
+    /// Removed from `layer` and not colliding with it anymore
+    pub const fn excluding(self, layer: CollisionLayer) -> Self {
+        CollisionLayers::new(self.memberships.without(layer), self.filters.without(layer))
+    }
+
+    pub const fn groups(self) -> CollisionGroups {
+        CollisionGroups {
+            memberships: self.memberships.0,
+            filters: self.filters.0,
+        }
+    }
+
+    pub const fn interaction_groups(self) -> InteractionGroups {
+        InteractionGroups::new(self.memberships.0, self.filters.0)
+    }
+
+    /// Same test Rapier does
+    pub const fn interacts(self, other: CollisionLayers) -> bool {
+        self.memberships.0 & other.filters.0 != 0 && other.memberships.0 & self.filters.0 != 0
+    }
+}
+
+/// Complains about layers that only collide one way or that the build cursor's ray can't hit anymore, in
+/// debug builds also logs which layers collide
+pub fn check_collision_layers() {
+    for a in CollisionLayer::ALL {
+        for b in CollisionLayer::ALL {
+            if a.interacts_with().contains(b) != b.interacts_with().contains(a) {
+                error!(
+                    "collision layers {:?} and {:?} only collide one way, check `interacts_with`",
+                    a, b
+                );
+            }
+        }
+    }
+
+    // Placing buildings depends on the cursor's ray hitting these
+    for layer in [
+        CollisionLayer::Terrain,
+        CollisionLayer::Building,
+        CollisionLayer::Blueprint,
+    ] {
+        if !CollisionLayers::of(CollisionLayer::Raycast).interacts(layer.colliders()) {
+            error!(
+                "the build cursor's ray doesn't hit the {:?} layer, buildings can't be placed on it",
+                layer
+            );
+        }
+    }
+
+    if cfg!(debug_assertions) {
+        info!("collision layers:\n{}", collision_layer_table());
+    }
+}
+
+/// Grid of which layers collide with each other, `x` where they do
+///
+/// Uses what the colliders in each layer are really in, so finished buildings show up as being in
+/// every layer
+pub fn collision_layer_table() -> String {
+    let width = CollisionLayer::ALL
+        .iter()
+        .map(|e| format!("{:?}", e).len())
+        .max()
+        .unwrap_or(0);
+
+    let mut table = format!("{:width$}", "", width = width);
+    for i in 0..CollisionLayer::ALL.len() {
+        table += &format!(" {}", i);
+    }
+
+    for (i, a) in CollisionLayer::ALL.iter().enumerate() {
+        table += &format!("\n{:width$}", format!("{:?}", a), width = width);
+        for b in CollisionLayer::ALL.iter() {
+            if a.colliders().interacts(b.colliders()) {
+                table += " x";
+            } else {
+                table += " .";
+            }
+        }
+        table += &format!("  {}", i);
+    }
+
+    table
+}
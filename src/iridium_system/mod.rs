@@ -0,0 +1,38 @@
+use bevy::{
+    core::FixedTimestep,
+    prelude::{App, ParallelSystemDescriptorCoercion, Plugin, SystemSet},
+};
+
+use self::{
+    network::{rebuild_iridium_networks, IridiumNetworks},
+    storage::{generate_iridium, init_iridium_storage},
+    transceiver::{
+        init_transceivers, pair_transceivers, relay_iridium, update_transceiver_prompts,
+        NextTransceiverId, PendingPairing,
+    },
+};
+
+pub mod network;
+pub mod storage;
+pub mod transceiver;
+
+pub struct IridiumSystemPlugin;
+
+impl Plugin for IridiumSystemPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(IridiumNetworks::default())
+            .init_resource::<NextTransceiverId>()
+            .init_resource::<PendingPairing>()
+            .add_system(init_iridium_storage)
+            .add_system(rebuild_iridium_networks)
+            .add_system(init_transceivers)
+            .add_system(pair_transceivers)
+            .add_system(update_transceiver_prompts.after(pair_transceivers))
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(1.0))
+                    .with_system(generate_iridium)
+                    .with_system(relay_iridium),
+            );
+    }
+}
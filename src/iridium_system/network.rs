@@ -0,0 +1,281 @@
+use std::hash::Hash;
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::building_system::building_components::{BuiltPipeEnd, SnappedTo};
+
+use super::{storage::IridiumStorage, transceiver::Transceiver};
+
+/// Groups of buildings that are connected by pipes, every building in a network shares its iridium
+///
+/// Networks joined by a transceiver pair count as connected, but keep their own iridium. It's moved
+/// between them over time by `relay_iridium`
+#[derive(Default)]
+pub struct IridiumNetworks {
+    network_of: HashMap<Entity, usize>,
+    networks: Vec<Vec<Entity>>,
+    /// The network every relayed network is joined into
+    relayed_to: HashMap<usize, usize>,
+}
+
+impl IridiumNetworks {
+    /// Builds the networks from pairs of connected entities
+    pub fn from_links(links: impl IntoIterator<Item = (Entity, Entity)>) -> Self {
+        let mut parents: HashMap<Entity, Entity> = HashMap::default();
+
+        for (a, b) in links {
+            let root_a = find_root(&mut parents, a);
+            let root_b = find_root(&mut parents, b);
+            if root_a != root_b {
+                parents.insert(root_a, root_b);
+            }
+        }
+
+        let mut networks = IridiumNetworks::default();
+        let mut network_of_root = HashMap::default();
+        let entities: Vec<Entity> = parents.keys().copied().collect();
+
+        for entity in entities {
+            let root = find_root(&mut parents, entity);
+            let id = *network_of_root.entry(root).or_insert_with(|| {
+                networks.networks.push(Vec::new());
+                networks.networks.len() - 1
+            });
+
+            networks.networks[id].push(entity);
+            networks.network_of.insert(entity, id);
+        }
+
+        networks
+    }
+
+    /// Joins the networks of each pair of entities (transceivers) into one logical network
+    pub fn with_relays(mut self, relays: impl IntoIterator<Item = (Entity, Entity)>) -> Self {
+        let mut parents: HashMap<usize, usize> = HashMap::default();
+
+        for (a, b) in relays {
+            let network_a = self.network_or_insert(a);
+            let network_b = self.network_or_insert(b);
+
+            let root_a = find_root(&mut parents, network_a);
+            let root_b = find_root(&mut parents, network_b);
+            if root_a != root_b {
+                parents.insert(root_a, root_b);
+            }
+        }
+
+        let networks: Vec<usize> = parents.keys().copied().collect();
+        for network in networks {
+            let root = find_root(&mut parents, network);
+            self.relayed_to.insert(network, root);
+        }
+
+        self
+    }
+
+    /// The network `entity` is in, entities that aren't connected to anything get a network of their own
+    fn network_or_insert(&mut self, entity: Entity) -> usize {
+        if let Some(id) = self.network_of.get(&entity) {
+            return *id;
+        }
+
+        self.networks.push(vec![entity]);
+        let id = self.networks.len() - 1;
+        self.network_of.insert(entity, id);
+        id
+    }
+
+    /// Every entity in the same network as `entity`, including itself
+    pub fn members(&self, entity: Entity) -> Vec<Entity> {
+        match self.network_of.get(&entity) {
+            Some(id) => self.networks[*id].clone(),
+            None => vec![entity],
+        }
+    }
+
+    /// Whether `a` and `b` share their iridium, either through pipes or a transceiver pair
+    pub fn connected(&self, a: Entity, b: Entity) -> bool {
+        let logical = |entity: Entity| {
+            self.network_of
+                .get(&entity)
+                .map(|id| *self.relayed_to.get(id).unwrap_or(id))
+        };
+
+        a == b
+            || matches!(
+                (logical(a), logical(b)),
+                (Some(x), Some(y)) if x == y
+            )
+    }
+
+    /// Total iridium that fits in the network that `entity` is in
+    pub fn capacity(&self, storage_query: &Query<&mut IridiumStorage>, entity: Entity) -> u32 {
+        self.members(entity)
+            .into_iter()
+            .filter_map(|e| storage_query.get(e).ok())
+            .map(|storage| storage.capacity)
+            .sum()
+    }
+
+    /// Total iridium stored in the network that `entity` is in
+    pub fn available(&self, storage_query: &Query<&mut IridiumStorage>, entity: Entity) -> u32 {
+        self.members(entity)
+            .into_iter()
+            .filter_map(|e| storage_query.get(e).ok())
+            .map(|storage| storage.current)
+            .sum()
+    }
+
+    /// Takes up to `amount` iridium from the network that `entity` is in
+    ///
+    /// Returns how much was actually taken
+    pub fn withdraw(
+        &self,
+        storage_query: &mut Query<&mut IridiumStorage>,
+        entity: Entity,
+        amount: u32,
+    ) -> u32 {
+        let mut remaining = amount;
+
+        for member in self.members(entity) {
+            if remaining == 0 {
+                break;
+            }
+            if let Ok(mut storage) = storage_query.get_mut(member) {
+                remaining -= storage.drain(remaining);
+            }
+        }
+
+        amount - remaining
+    }
+
+    /// Puts up to `amount` iridium into the network that `entity` is in
+    ///
+    /// Returns how much actually fit
+    pub fn deposit(
+        &self,
+        storage_query: &mut Query<&mut IridiumStorage>,
+        entity: Entity,
+        amount: u32,
+    ) -> u32 {
+        let mut remaining = amount;
+
+        for member in self.members(entity) {
+            if remaining == 0 {
+                break;
+            }
+            if let Ok(mut storage) = storage_query.get_mut(member) {
+                remaining -= storage.fill(remaining);
+            }
+        }
+
+        amount - remaining
+    }
+}
+
+fn find_root<T: Copy + Eq + Hash>(parents: &mut HashMap<T, T>, entity: T) -> T {
+    let parent = *parents.entry(entity).or_insert(entity);
+    if parent == entity {
+        return entity;
+    }
+
+    let root = find_root(parents, parent);
+    parents.insert(entity, root);
+    root
+}
+
+/// Links every built pipe to the buildings its ends are snapped to, then joins the networks of
+/// powered transceiver pairs
+pub fn rebuild_iridium_networks(
+    mut networks: ResMut<IridiumNetworks>,
+
+    pipe_end_query: Query<(&Parent, &SnappedTo), With<BuiltPipeEnd>>,
+    transceiver_query: Query<(Entity, &Transceiver)>,
+) {
+    let transceivers: HashMap<u32, Entity> = transceiver_query
+        .iter()
+        .map(|(entity, transceiver)| (transceiver.id, entity))
+        .collect();
+
+    let relays = transceiver_query
+        .iter()
+        .filter(|(_, transceiver)| transceiver.powered)
+        .filter_map(|(entity, transceiver)| {
+            transceivers
+                .get(&transceiver.partner?)
+                .map(|partner| (entity, *partner))
+        });
+
+    *networks = IridiumNetworks::from_links(
+        pipe_end_query
+            .iter()
+            .map(|(pipe, snapped)| (pipe.0, snapped.0)),
+    )
+    .with_relays(relays);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn e(id: u32) -> Entity {
+        Entity::from_raw(id)
+    }
+
+    fn sorted(mut entities: Vec<Entity>) -> Vec<Entity> {
+        entities.sort();
+        entities
+    }
+
+    #[test]
+    fn links_join_into_networks() {
+        // 1 - 2 - 3 and 4 - 5, linked in an order that needs the roots merged
+        let networks = IridiumNetworks::from_links([(e(1), e(2)), (e(4), e(5)), (e(3), e(2))]);
+
+        assert_eq!(sorted(networks.members(e(3))), vec![e(1), e(2), e(3)]);
+        assert_eq!(sorted(networks.members(e(5))), vec![e(4), e(5)]);
+        assert!(networks.connected(e(1), e(3)));
+        assert!(!networks.connected(e(1), e(4)));
+    }
+
+    #[test]
+    fn unlinked_entities_are_on_their_own() {
+        let networks = IridiumNetworks::from_links([(e(1), e(2))]);
+
+        assert_eq!(networks.members(e(9)), vec![e(9)]);
+        assert!(networks.connected(e(9), e(9)));
+        assert!(!networks.connected(e(1), e(9)));
+    }
+
+    #[test]
+    fn linking_a_network_to_itself_changes_nothing() {
+        let networks =
+            IridiumNetworks::from_links([(e(1), e(2)), (e(2), e(3)), (e(3), e(1)), (e(2), e(2))]);
+
+        assert_eq!(sorted(networks.members(e(1))), vec![e(1), e(2), e(3)]);
+    }
+
+    #[test]
+    fn relays_connect_networks_but_keep_their_iridium_apart() {
+        // Transceivers 2 and 4 pair 1 - 2 with 3 - 4, 5 and 6 are paired but not on any pipe
+        let networks = IridiumNetworks::from_links([(e(1), e(2)), (e(3), e(4))]).with_relays([
+            (e(2), e(4)),
+            (e(4), e(2)),
+            (e(5), e(6)),
+        ]);
+
+        assert!(networks.connected(e(1), e(3)));
+        assert!(networks.connected(e(5), e(6)));
+        assert!(!networks.connected(e(1), e(5)));
+        assert_eq!(sorted(networks.members(e(1))), vec![e(1), e(2)]);
+        assert_eq!(networks.members(e(6)), vec![e(6)]);
+    }
+
+    #[test]
+    fn relays_chain_through_several_networks() {
+        let networks = IridiumNetworks::from_links([(e(1), e(2)), (e(3), e(4)), (e(5), e(6))])
+            .with_relays([(e(2), e(3)), (e(4), e(5))]);
+
+        assert!(networks.connected(e(1), e(6)));
+    }
+}
@@ -0,0 +1,65 @@
+use bevy::prelude::*;
+
+use crate::building_system::blueprint::BuildingCompletedEvent;
+
+/// Iridium stored inside a finished building
+///
+/// Same units as building costs (hundredths of an iridium)
+#[derive(Component, Debug)]
+pub struct IridiumStorage {
+    pub current: u32,
+    pub capacity: u32,
+}
+
+impl IridiumStorage {
+    /// Adds up to `amount` and returns how much actually fit
+    pub fn fill(&mut self, amount: u32) -> u32 {
+        let added = amount.min(self.capacity - self.current);
+        self.current += added;
+        added
+    }
+
+    /// Takes up to `amount` and returns how much was taken
+    pub fn drain(&mut self, amount: u32) -> u32 {
+        let taken = amount.min(self.current);
+        self.current -= taken;
+        taken
+    }
+}
+
+/// Buildings with this component make this much iridium every second
+#[derive(Component)]
+pub struct IridiumGenerator(pub u32);
+
+/// Gives finished buildings their storage and generation
+pub fn init_iridium_storage(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+
+    storage_query: Query<(), With<IridiumStorage>>,
+) {
+    for event in completed_events.iter() {
+        let iridium_data = &event.building.iridium_data;
+        let mut entity_commands = commands.entity(event.entity);
+
+        // Loaded buildings already have the iridium they were saved with
+        let loaded = storage_query.contains(event.entity);
+        if let Some(capacity) = iridium_data.storage.filter(|e| *e > 0 && !loaded) {
+            entity_commands.insert(IridiumStorage {
+                current: iridium_data.current.unwrap_or(0).min(capacity),
+                capacity,
+            });
+        }
+
+        if let Some(generation) = iridium_data.generation.filter(|e| *e > 0) {
+            entity_commands.insert(IridiumGenerator(generation));
+        }
+    }
+}
+
+/// Runs once every second
+pub fn generate_iridium(mut generator_query: Query<(&IridiumGenerator, &mut IridiumStorage)>) {
+    for (generator, mut storage) in generator_query.iter_mut() {
+        storage.fill(generator.0);
+    }
+}
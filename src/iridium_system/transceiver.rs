@@ -0,0 +1,206 @@
+use bevy::{prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    building_system::{blueprint::BuildingCompletedEvent, buildings::BuildingType},
+    player_system::interaction::{InteractEvent, Interactable},
+    settings::GameSettings,
+};
+
+use super::{network::IridiumNetworks, storage::IridiumStorage};
+
+/// A finished transceiver
+///
+/// Pairs are stored by `id` instead of by entity so they can be saved and loaded
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Transceiver {
+    pub id: u32,
+    pub partner: Option<u32>,
+
+    /// Whether both sides paid their upkeep at the last relay tick, unpowered pairs don't join networks
+    #[serde(skip)]
+    pub powered: bool,
+}
+
+/// The id the next finished transceiver gets
+#[derive(Default, Serialize, Deserialize)]
+pub struct NextTransceiverId(pub u32);
+
+/// The transceiver that was used first while pairing, the next one used gets paired with it
+#[derive(Default)]
+pub struct PendingPairing(pub Option<Entity>);
+
+pub fn init_transceivers(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    mut next_id: ResMut<NextTransceiverId>,
+
+    transceiver_query: Query<(), With<Transceiver>>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::Transceiver {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(event.entity);
+        entity_commands.insert(Interactable::new("prompt.pair"));
+
+        // Loaded transceivers keep the id and partner they were saved with
+        if !transceiver_query.contains(event.entity) {
+            entity_commands.insert(Transceiver {
+                id: next_id.0,
+                partner: None,
+                powered: false,
+            });
+            next_id.0 += 1;
+        }
+    }
+}
+
+/// Using a transceiver selects it, using a second one pairs them. Using the selected one again cancels,
+/// using one that's already paired (with nothing selected) unpairs it
+pub fn pair_transceivers(
+    mut interact_events: EventReader<InteractEvent>,
+    mut pending: ResMut<PendingPairing>,
+
+    mut transceiver_query: Query<(Entity, &mut Transceiver)>,
+) {
+    for event in interact_events.iter() {
+        let (id, partner) = match transceiver_query.get(event.entity) {
+            Ok((_, e)) => (e.id, e.partner),
+            Err(_) => continue,
+        };
+
+        match pending.0 {
+            Some(first) if first == event.entity => {
+                pending.0 = None;
+            }
+            Some(first) => {
+                let first_id = match transceiver_query.get(first) {
+                    Ok((_, e)) => e.id,
+                    Err(_) => {
+                        // The selected one was destroyed, start over from this one
+                        pending.0 = Some(event.entity);
+                        continue;
+                    }
+                };
+
+                // Whatever either of them was paired with loses its partner
+                for (_, mut transceiver) in transceiver_query.iter_mut() {
+                    if transceiver.id == first_id || transceiver.id == id {
+                        transceiver.partner = Some(match transceiver.id == id {
+                            true => first_id,
+                            false => id,
+                        });
+                        transceiver.powered = false;
+                    } else if matches!(transceiver.partner, Some(e) if e == first_id || e == id) {
+                        transceiver.partner = None;
+                        transceiver.powered = false;
+                    }
+                }
+
+                info!("paired transceivers {} and {}", first_id, id);
+                pending.0 = None;
+            }
+            None if partner.is_some() => {
+                for (_, mut transceiver) in transceiver_query.iter_mut() {
+                    if transceiver.id == id || transceiver.partner == Some(id) {
+                        transceiver.partner = None;
+                        transceiver.powered = false;
+                    }
+                }
+            }
+            None => {
+                pending.0 = Some(event.entity);
+            }
+        }
+    }
+}
+
+/// Keeps the "Press E to ..." text of transceivers in line with what using them would do
+pub fn update_transceiver_prompts(
+    pending: Res<PendingPairing>,
+    mut transceiver_query: Query<(Entity, &Transceiver, &mut Interactable)>,
+) {
+    for (entity, transceiver, mut interactable) in transceiver_query.iter_mut() {
+        let prompt = match pending.0 {
+            Some(e) if e == entity => "prompt.cancel_pairing",
+            Some(_) => "prompt.pair_with_selected",
+            None if transceiver.partner.is_some() => "prompt.unpair",
+            None => "prompt.pair",
+        };
+
+        if interactable.prompt != prompt {
+            interactable.prompt = prompt.to_string();
+        }
+    }
+}
+
+/// Runs once every second
+///
+/// Both sides of a pair pay their upkeep, then up to `GameSettings::transceiver_throughput` iridium is
+/// moved from the fuller network to the emptier one
+pub fn relay_iridium(
+    networks: Res<IridiumNetworks>,
+    settings: Res<GameSettings>,
+
+    mut transceiver_query: Query<(Entity, &mut Transceiver)>,
+    mut storage_query: Query<&mut IridiumStorage>,
+) {
+    let transceivers: HashMap<u32, Entity> = transceiver_query
+        .iter()
+        .map(|(entity, transceiver)| (transceiver.id, entity))
+        .collect();
+
+    // Each pair once
+    let pairs: Vec<(Entity, Entity)> = transceiver_query
+        .iter()
+        .filter_map(|(entity, transceiver)| {
+            let partner = transceiver.partner.filter(|e| transceiver.id < *e)?;
+            transceivers.get(&partner).map(|e| (entity, *e))
+        })
+        .collect();
+
+    for (a, b) in pairs {
+        let powered = networks.available(&storage_query, a) >= settings.transceiver_upkeep
+            && networks.available(&storage_query, b) >= settings.transceiver_upkeep;
+
+        for entity in [a, b] {
+            transceiver_query.get_mut(entity).unwrap().1.powered = powered;
+        }
+
+        if !powered {
+            continue;
+        }
+
+        networks.withdraw(&mut storage_query, a, settings.transceiver_upkeep);
+        networks.withdraw(&mut storage_query, b, settings.transceiver_upkeep);
+
+        // Both ends are on the same pipes, there's nothing to move
+        if networks.members(a).contains(&b) {
+            continue;
+        }
+
+        let available = [a, b].map(|e| networks.available(&storage_query, e) as u64);
+        let capacity = [a, b].map(|e| networks.capacity(&storage_query, e) as u64);
+        let total_capacity = capacity[0] + capacity[1];
+        if total_capacity == 0 {
+            continue;
+        }
+
+        // How much `a` would have if both sides were equally full
+        let balanced = (available[0] + available[1]) * capacity[0] / total_capacity;
+
+        let (from, to, amount) = match available[0] > balanced {
+            true => (a, b, available[0] - balanced),
+            false => (b, a, balanced - available[0]),
+        };
+        let amount = amount.min(settings.transceiver_throughput as u64) as u32;
+
+        let taken = networks.withdraw(&mut storage_query, from, amount);
+        let given = networks.deposit(&mut storage_query, to, taken);
+
+        // Put back whatever didn't fit
+        networks.deposit(&mut storage_query, from, taken - given);
+    }
+}
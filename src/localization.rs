@@ -0,0 +1,211 @@
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+
+use crate::settings::GameSettings;
+
+/// Folder (in `assets`) the string tables are loaded from, one `<language>.lang` file per language
+pub const LOCALE_FOLDER: &str = "locale";
+
+/// Used for every key the selected language doesn't have
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// One language's strings, from a `<language>.lang` file
+#[derive(TypeUuid, Debug)]
+#[uuid = "3f8a2d61-7c4e-4b9a-a5d3-1e6f0c8b2d47"]
+pub struct StringTable(pub HashMap<String, String>);
+
+/// Every string the player sees, in the language picked in `GameSettings::language`
+///
+/// The files are `key = value` lines, `#` starts a comment. `{name}` in a value is replaced by
+/// `Localization::format`. Every string shows its key until `apply_string_tables` has the tables
+pub struct Localization {
+    language: String,
+    strings_handle: Option<Handle<StringTable>>,
+    fallback_handle: Handle<StringTable>,
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+}
+
+impl FromWorld for Localization {
+    fn from_world(world: &mut World) -> Self {
+        let language = world
+            .get_resource::<GameSettings>()
+            .expect("GameSettings has to be inserted before Localization")
+            .language
+            .clone();
+        let asset_server = world.resource::<AssetServer>();
+
+        let load =
+            |language: &str| asset_server.load(&format!("{}/{}.lang", LOCALE_FOLDER, language));
+
+        Localization {
+            strings_handle: match language == FALLBACK_LANGUAGE {
+                true => None,
+                false => Some(load(&language)),
+            },
+            fallback_handle: load(FALLBACK_LANGUAGE),
+            language,
+            strings: HashMap::default(),
+            fallback: HashMap::default(),
+        }
+    }
+}
+
+impl Localization {
+    /// Uses the loaded tables, warns about the strings the selected language is missing
+    fn set_tables(&mut self, strings: HashMap<String, String>, fallback: HashMap<String, String>) {
+        if !strings.is_empty() {
+            let mut missing: Vec<&String> = fallback
+                .keys()
+                .filter(|e| !strings.contains_key(*e))
+                .collect();
+            missing.sort();
+
+            if !missing.is_empty() {
+                warn!(
+                    "{:?} is missing {} strings, they're shown in {:?}: {}",
+                    self.language,
+                    missing.len(),
+                    FALLBACK_LANGUAGE,
+                    missing
+                        .iter()
+                        .map(|e| e.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+
+        self.strings = strings;
+        self.fallback = fallback;
+    }
+
+    /// `true` once the English table is loaded
+    pub fn is_loaded(&self) -> bool {
+        !self.fallback.is_empty()
+    }
+
+    /// `true` if either the selected language or English has `key`
+    pub fn has(&self, key: &str) -> bool {
+        self.strings.contains_key(key) || self.fallback.contains_key(key)
+    }
+
+    /// The string of `key`, in English if the selected language doesn't have it and the key itself if
+    /// English doesn't either
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.get_or(key, key)
+    }
+
+    /// Same as `get` but with `default` instead of the key, for names that also come from data files
+    pub fn get_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(default, |e| e.as_str())
+    }
+
+    /// The string of `key` with every `{name}` replaced by its value in `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut value = self.get(key).to_string();
+        for (name, arg) in args {
+            value = value.replace(&format!("{{{}}}", name), &arg.to_string());
+        }
+        value
+    }
+}
+
+/// Copies the string tables into `Localization` once they're loaded (and again when they're edited)
+///
+/// Tables that can't be read are logged by the asset server, the selected language falls back to
+/// English and English to the keys
+pub fn apply_string_tables(
+    mut table_events: EventReader<AssetEvent<StringTable>>,
+    tables: Res<Assets<StringTable>>,
+    mut localization: ResMut<Localization>,
+) {
+    let changed = table_events.iter().any(|e| match e {
+        AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+            *handle == localization.fallback_handle
+                || localization.strings_handle.as_ref() == Some(handle)
+        }
+        AssetEvent::Removed { .. } => false,
+    });
+    if !changed {
+        return;
+    }
+
+    let table = |handle: &Handle<StringTable>| {
+        tables
+            .get(handle)
+            .map_or_else(HashMap::default, |e| e.0.clone())
+    };
+    let strings = localization
+        .strings_handle
+        .as_ref()
+        .map_or_else(HashMap::default, table);
+    let fallback = table(&localization.fallback_handle);
+
+    localization.set_tables(strings, fallback);
+}
+
+/// Reads `*.lang` files, lines that aren't `key = value` are logged and skipped
+#[derive(Default)]
+pub struct StringTableLoader;
+
+impl AssetLoader for StringTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let path = load_context.path();
+            let text = std::str::from_utf8(bytes).map_err(|e| anyhow!("{:?}: {}", path, e))?;
+
+            let table = parse_table(&format!("{:?}", path), text);
+            load_context.set_default_asset(LoadedAsset::new(StringTable(table)));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang"]
+    }
+}
+
+/// `path` is only used in the logs
+fn parse_table(path: &str, text: &str) -> HashMap<String, String> {
+    let mut table = HashMap::default();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => {
+                if table
+                    .insert(key.trim().to_string(), value.trim().to_string())
+                    .is_some()
+                {
+                    warn!(
+                        "{}:{}: {:?} is defined more than once, the last one is used",
+                        path,
+                        i + 1,
+                        key.trim()
+                    );
+                }
+            }
+            _ => error!("{}:{}: expected `key = value`", path, i + 1),
+        }
+    }
+
+    table
+}
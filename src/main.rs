@@ -30,17 +30,29 @@ use bevy_rapier3d::{
     prelude::{NoUserData, RapierConfiguration, RapierPhysicsPlugin},
 };
 use building_system::{BuildingSystemPlugin, RaycastSet};
-use constants::HALF_SIZE;
+use collision_layers::check_collision_layers;
+use iridium_system::IridiumSystemPlugin;
+use localization::{apply_string_tables, Localization, StringTable, StringTableLoader};
+use military_system::MilitarySystemPlugin;
 use player_system::PlayerSystemPlugin;
+use save_system::SaveSystemPlugin;
+use settings::{watch_definition_files, GameSettings, GAME_SETTINGS_PATH};
+use structure_system::StructureSystemPlugin;
 use terrain_generation_system::GeneratorPlugin;
 
 pub mod building_system;
+pub mod iridium_system;
+pub mod military_system;
 pub mod player_system;
+pub mod save_system;
+pub mod structure_system;
 pub mod terrain_generation_system;
 
 pub mod algorithms;
+pub mod collision_layers;
 pub mod constants;
-pub mod gui_branch_builder;
+pub mod localization;
+pub mod settings;
 
 pub mod material_palette;
 pub mod model_loader;
@@ -53,11 +65,23 @@ fn main() {
         .add_plugin(FrameTimeDiagnosticsPlugin::default())
         .add_plugin(ObjPlugin)
         .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
+        // read before the game's plugins, they can use it while they're built
+        .insert_resource(GameSettings::load(GAME_SETTINGS_PATH))
+        .add_asset::<StringTable>()
+        .init_asset_loader::<StringTableLoader>()
+        .init_resource::<Localization>()
+        .add_system_to_stage(CoreStage::PreUpdate, apply_string_tables)
         .add_plugin(GeneratorPlugin)
         .add_plugin(BuildingSystemPlugin)
+        .add_plugin(IridiumSystemPlugin)
+        .add_plugin(MilitarySystemPlugin)
+        .add_plugin(StructureSystemPlugin)
         .add_plugin(PlayerSystemPlugin)
+        .add_plugin(SaveSystemPlugin)
         // startup system
+        .add_startup_system_to_stage(StartupStage::PreStartup, watch_definition_files)
         .add_startup_system(startup)
+        .add_startup_system(check_collision_layers)
         // resources
         .insert_resource(RapierConfiguration {
             gravity: [0.0, -9.81, 0.0].into(),
@@ -82,18 +106,20 @@ fn startup(
 
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut meshes: ResMut<Assets<Mesh>>,
+    settings: Res<GameSettings>,
 ) {
     info!("startup start");
+    let half_size = settings.half_size;
     commands.spawn_bundle(DirectionalLightBundle {
         directional_light: DirectionalLight {
             // Configure the projection to better fit the scene
             shadow_projection: OrthographicProjection {
-                left: -HALF_SIZE,
-                right: HALF_SIZE,
-                bottom: -HALF_SIZE,
-                top: HALF_SIZE,
-                near: -10.0 * HALF_SIZE,
-                far: 10.0 * HALF_SIZE,
+                left: -half_size,
+                right: half_size,
+                bottom: -half_size,
+                top: half_size,
+                near: -10.0 * half_size,
+                far: 10.0 * half_size,
                 ..Default::default()
             },
             shadows_enabled: true,
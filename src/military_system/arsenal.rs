@@ -0,0 +1,456 @@
+use std::collections::VecDeque;
+
+use anyhow::{anyhow, bail};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashSet},
+};
+use serde::Deserialize;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent,
+        buildings::{BuildingReferenceComponent, BuildingType},
+        raycasting::BuildCursor,
+    },
+    iridium_system::{network::IridiumNetworks, storage::IridiumStorage},
+    localization::Localization,
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput, InputBindings},
+        inventory::Inventory,
+        player::Player,
+    },
+    settings::GameSettings,
+};
+
+/// Everything the arsenal can make, loaded from `assets/data/arsenal.recipes.ron`
+#[derive(Deserialize, TypeUuid, Debug)]
+#[uuid = "5c3e9a52-8f0d-4b8e-9f53-2d1b7c6a4e10"]
+pub struct RecipeTable {
+    pub recipes: Vec<Recipe>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct Recipe {
+    /// Id of the item this makes, must be unique
+    pub id: String,
+    /// Shown if the string table doesn't have `recipe.<id>`
+    pub name: String,
+    /// How many items a single job makes
+    pub amount: u32,
+    pub iridium_cost: u32,
+    /// How long a job takes if the iridium supply keeps up
+    pub seconds: f32,
+}
+
+impl Recipe {
+    /// Name of the recipe in the selected language
+    pub fn name<'a>(&'a self, localization: &'a Localization) -> &'a str {
+        localization.get_or(&format!("recipe.{}", self.id), &self.name)
+    }
+}
+
+impl RecipeTable {
+    pub fn get(&self, id: &str) -> Option<&Recipe> {
+        self.recipes.iter().find(|e| e.id == id)
+    }
+}
+
+#[derive(Default)]
+pub struct RecipeTableLoader;
+
+impl AssetLoader for RecipeTableLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let table: RecipeTable = ron::de::from_bytes(bytes)
+                .map_err(|e| anyhow!("{:?}: {}", load_context.path(), e))?;
+
+            let mut ids = HashSet::default();
+            for recipe in table.recipes.iter() {
+                if !ids.insert(recipe.id.clone()) {
+                    bail!(
+                        "{:?}: duplicate recipe id \"{}\"",
+                        load_context.path(),
+                        recipe.id
+                    );
+                }
+                if recipe.seconds <= 0.0 {
+                    bail!(
+                        "{:?}: recipe \"{}\" must take more than 0 seconds",
+                        load_context.path(),
+                        recipe.id
+                    );
+                }
+            }
+
+            load_context.set_default_asset(LoadedAsset::new(table));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["recipes.ron"]
+    }
+}
+
+pub struct ArsenalRecipes(pub Handle<RecipeTable>);
+
+pub struct ProductionJob {
+    pub recipe: String,
+    pub paid: u32,
+
+    /// Iridium owed by this job that hasn't been paid yet
+    carry: f32,
+}
+
+impl ProductionJob {
+    /// Owes the iridium for `seconds` more of work on `recipe` and pays what `withdraw` gives out of it
+    ///
+    /// Returns `true` once the job is paid in full
+    pub fn advance(
+        &mut self,
+        recipe: &Recipe,
+        seconds: f32,
+        withdraw: impl FnOnce(u32) -> u32,
+    ) -> bool {
+        self.carry += recipe.iridium_cost as f32 * seconds / recipe.seconds;
+
+        // The recipe can be made cheaper by a hot reload while the job is being paid
+        let owed = (self.carry as u32).min(recipe.iridium_cost.saturating_sub(self.paid));
+        let paid = withdraw(owed);
+
+        self.paid += paid;
+        self.carry -= paid as f32;
+
+        // Don't let a stalled job save up and then drain the network all at once
+        if paid < owed {
+            self.carry = self.carry.min(1.0);
+        }
+
+        self.paid >= recipe.iridium_cost
+    }
+}
+
+/// Jobs waiting in a finished arsenal, the front job is the one being worked on
+#[derive(Component, Default)]
+pub struct ProductionQueue {
+    pub jobs: VecDeque<ProductionJob>,
+}
+
+impl ProductionQueue {
+    /// Adds a job to the back of the queue, returns `false` if it already has `max_jobs`
+    pub fn push(&mut self, recipe: &Recipe, max_jobs: usize) -> bool {
+        if self.jobs.len() >= max_jobs {
+            return false;
+        }
+
+        self.jobs.push_back(ProductionJob {
+            recipe: recipe.id.clone(),
+            paid: 0,
+            carry: 0.0,
+        });
+        true
+    }
+}
+
+/// The text that shows the queue and stock of the arsenal under the cursor
+#[derive(Component)]
+pub struct ArsenalPanel;
+
+pub fn arsenal_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(ArsenalRecipes(
+        asset_server.load("data/arsenal.recipes.ron"),
+    ));
+
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    top: Val::Px(14.0),
+                    right: Val::Px(14.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/zekton-rg.ttf"),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                Default::default(),
+            ),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(ArsenalPanel);
+}
+
+/// Gives finished arsenals their queue and inventory
+pub fn init_arsenals(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type == BuildingType::Arsenal {
+            commands
+                .entity(event.entity)
+                .insert_bundle((ProductionQueue::default(), Inventory::default()));
+        }
+    }
+}
+
+/// With the cursor over an arsenal, number keys queue recipes and F collects what it has made
+pub fn arsenal_input(
+    input: ActionInput,
+    settings: Res<GameSettings>,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+    recipe_tables: Res<Assets<RecipeTable>>,
+    arsenal_recipes: Res<ArsenalRecipes>,
+
+    parent_query: Query<&Parent>,
+    mut arsenal_query: Query<(&mut ProductionQueue, &mut Inventory), Without<Player>>,
+    mut player_query: Query<&mut Inventory, With<Player>>,
+) {
+    if selected_building.id.is_some() {
+        return;
+    }
+
+    let (mut queue, mut stock) = match build_cursor
+        .hovered_entity(&parent_query)
+        .and_then(|e| arsenal_query.get_mut(e).ok())
+    {
+        Some(e) => e,
+        None => return,
+    };
+
+    if let Some(table) = recipe_tables.get(&arsenal_recipes.0) {
+        for (action, recipe) in Action::RECIPES.iter().zip(table.recipes.iter()) {
+            if input.just_pressed(*action) && !queue.push(recipe, settings.arsenal_queue_size) {
+                info!("arsenal queue is full");
+            }
+        }
+    }
+
+    if input.just_pressed(Action::UseBuilding) {
+        if let Ok(mut inventory) = player_query.get_single_mut() {
+            inventory.take_all(&mut stock);
+        }
+    }
+}
+
+/// Pays for the front job of every arsenal a bit at a time and puts the items in its inventory once it's paid off
+///
+/// Jobs stall while the arsenal's network doesn't have any iridium
+pub fn run_production(
+    time: Res<Time>,
+    networks: Res<IridiumNetworks>,
+    recipe_tables: Res<Assets<RecipeTable>>,
+    arsenal_recipes: Res<ArsenalRecipes>,
+
+    mut storage_query: Query<&mut IridiumStorage>,
+    mut arsenal_query: Query<(Entity, &mut ProductionQueue, &mut Inventory)>,
+) {
+    let table = match recipe_tables.get(&arsenal_recipes.0) {
+        Some(e) => e,
+        None => return,
+    };
+
+    for (entity, mut queue, mut stock) in arsenal_query.iter_mut() {
+        let job = match queue.jobs.front_mut() {
+            Some(e) => e,
+            None => continue,
+        };
+
+        let recipe = match table.get(&job.recipe) {
+            Some(e) => e,
+            None => {
+                warn!("dropping job for unknown recipe \"{}\"", job.recipe);
+                queue.jobs.pop_front();
+                continue;
+            }
+        };
+
+        let done = job.advance(recipe, time.delta_seconds(), |owed| {
+            networks.withdraw(&mut storage_query, entity, owed)
+        });
+
+        if done {
+            stock.add(&recipe.id, recipe.amount);
+            queue.jobs.pop_front();
+        }
+    }
+}
+
+pub fn update_arsenal_panel(
+    build_cursor: Res<BuildCursor>,
+    input_bindings: Res<InputBindings>,
+    localization: Res<Localization>,
+    recipe_tables: Res<Assets<RecipeTable>>,
+    arsenal_recipes: Res<ArsenalRecipes>,
+
+    parent_query: Query<&Parent>,
+    arsenal_query: Query<(&BuildingReferenceComponent, &ProductionQueue, &Inventory)>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<ArsenalPanel>>,
+) {
+    let (mut text, mut visibility) = panel_query.single_mut();
+
+    let hovered = build_cursor
+        .hovered_entity(&parent_query)
+        .and_then(|e| arsenal_query.get(e).ok());
+
+    let (building_ref, queue, stock, table) = match (hovered, recipe_tables.get(&arsenal_recipes.0))
+    {
+        (Some((building_ref, queue, stock)), Some(table)) => (building_ref, queue, stock, table),
+        _ => {
+            visibility.is_visible = false;
+            return;
+        }
+    };
+
+    let mut value = format!("{}\n", building_ref.0.building_id.name(&localization));
+
+    for (action, recipe) in Action::RECIPES.iter().zip(table.recipes.iter()) {
+        value += &localization.format(
+            "arsenal.recipe",
+            &[
+                ("key", &input_bindings.label(*action)),
+                ("name", &recipe.name(&localization)),
+                ("amount", &recipe.amount),
+                ("cost", &(recipe.iridium_cost / 100)),
+            ],
+        );
+        value += "\n";
+    }
+
+    value += &format!("\n{}\n", localization.get("arsenal.queue"));
+    for (i, job) in queue.jobs.iter().enumerate() {
+        if let Some(recipe) = table.get(&job.recipe) {
+            match i {
+                0 => {
+                    value += &localization.format(
+                        "arsenal.job",
+                        &[
+                            ("name", &recipe.name(&localization)),
+                            ("percent", &(job.paid * 100 / recipe.iridium_cost.max(1))),
+                        ],
+                    );
+                    value += "\n";
+                }
+                _ => value += &format!("{}\n", recipe.name(&localization)),
+            }
+        }
+    }
+
+    value += "\n";
+    value += &localization.format(
+        "arsenal.stock",
+        &[("key", &input_bindings.label(Action::UseBuilding))],
+    );
+    value += "\n";
+    for recipe in table.recipes.iter() {
+        let count = stock.count(&recipe.id);
+        if count > 0 {
+            value += &localization.format(
+                "arsenal.stock_item",
+                &[("name", &recipe.name(&localization)), ("count", &count)],
+            );
+            value += "\n";
+        }
+    }
+
+    text.sections[0].value = value;
+    visibility.is_visible = true;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipe(iridium_cost: u32, seconds: f32) -> Recipe {
+        Recipe {
+            id: "ammo".to_string(),
+            name: "Ammunition".to_string(),
+            amount: 30,
+            iridium_cost,
+            seconds,
+        }
+    }
+
+    fn job() -> ProductionJob {
+        ProductionJob {
+            recipe: "ammo".to_string(),
+            paid: 0,
+            carry: 0.0,
+        }
+    }
+
+    #[test]
+    fn queue_stops_at_max_jobs() {
+        let recipe = recipe(100, 1.0);
+        let mut queue = ProductionQueue::default();
+
+        assert!(queue.push(&recipe, 2));
+        assert!(queue.push(&recipe, 2));
+        assert!(!queue.push(&recipe, 2));
+        assert_eq!(queue.jobs.len(), 2);
+        assert!(queue.jobs.iter().all(|e| e.recipe == "ammo" && e.paid == 0));
+    }
+
+    #[test]
+    fn jobs_are_paid_over_the_recipe_time() {
+        let recipe = recipe(100, 2.0);
+        let mut job = job();
+
+        assert!(!job.advance(&recipe, 1.0, |owed| owed));
+        assert_eq!(job.paid, 50);
+        assert!(job.advance(&recipe, 1.0, |owed| owed));
+        assert_eq!(job.paid, 100);
+    }
+
+    #[test]
+    fn stalled_jobs_dont_save_up() {
+        let recipe = recipe(100, 2.0);
+        let mut job = job();
+
+        assert!(!job.advance(&recipe, 10.0, |_| 0));
+        job.advance(&recipe, 0.0, |owed| owed);
+        assert!(job.paid <= 1);
+    }
+
+    #[test]
+    fn recipe_cheaper_than_what_was_paid() {
+        let mut job = job();
+        job.advance(&recipe(100, 1.0), 0.8, |owed| owed);
+        assert_eq!(job.paid, 80);
+
+        // Reloaded with a lower cost
+        let mut owed = None;
+        assert!(job.advance(&recipe(50, 1.0), 1.0, |e| {
+            owed = Some(e);
+            e
+        }));
+        assert_eq!(owed, Some(0));
+    }
+
+    #[test]
+    fn shipped_recipes_parse() {
+        let table: RecipeTable =
+            ron::de::from_str(include_str!("../../assets/data/arsenal.recipes.ron")).unwrap();
+
+        let ids: HashSet<&str> = table.recipes.iter().map(|e| e.id.as_str()).collect();
+        assert_eq!(ids.len(), table.recipes.len());
+        assert!(table.recipes.iter().all(|e| e.seconds > 0.0));
+        assert!(table.get("ammo").is_some());
+    }
+}
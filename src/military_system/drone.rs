@@ -0,0 +1,259 @@
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+use bevy::{math::const_vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        building::Pipe,
+        building_components::{PlacedBlueprint, SegmentBlueprint},
+    },
+    collision_layers::{CollisionLayer, CollisionLayers},
+    iridium_system::{
+        network::IridiumNetworks,
+        storage::{IridiumGenerator, IridiumStorage},
+    },
+    settings::GameSettings,
+};
+
+use super::logistics::{Demand, DroneJobs, IdleDrone, Supply};
+
+/// Where drones fly to, relative to the building they're visiting
+const HOVER_OFFSET: Vec3 = const_vec3!([0.0, 2.5, 0.0]);
+
+/// How close a drone has to get to its hover point to count as there
+const ARRIVE_DISTANCE: f32 = 0.75;
+
+/// The rays drones steer with, they only hit the terrain
+const DRONE_RAY: InteractionGroups =
+    CollisionLayers::of(CollisionLayer::DroneRay).interaction_groups();
+
+/// A drone made by a S.S.I.M.
+#[derive(Component)]
+pub struct Drone {
+    /// The S.S.I.M. that made it, idle drones wait above it
+    pub home: Entity,
+    pub carrying: u32,
+    velocity: Vec3,
+}
+
+impl Drone {
+    pub fn new(home: Entity) -> Self {
+        Drone {
+            home,
+            carrying: 0,
+            velocity: Vec3::ZERO,
+        }
+    }
+}
+
+/// Hands out trips to drones without one, runs a couple of times a second
+///
+/// Wells (anything generating iridium) are the supplies, blueprints (placed and two-point ones) and other
+/// storage are the demands
+pub fn assign_drone_jobs(
+    mut jobs: ResMut<DroneJobs>,
+    networks: Res<IridiumNetworks>,
+    settings: Res<GameSettings>,
+
+    drone_query: Query<(Entity, &Drone, &Transform)>,
+    supply_query: Query<(Entity, &IridiumStorage, &Transform), With<IridiumGenerator>>,
+    tank_query: Query<(Entity, &IridiumStorage, &Transform), Without<IridiumGenerator>>,
+    blueprint_query: Query<(Entity, &PlacedBlueprint, &Transform)>,
+    segment_query: Query<(Entity, &SegmentBlueprint, &Pipe)>,
+) {
+    jobs.retain(|e| drone_query.contains(e));
+
+    let supplies: Vec<Supply> = supply_query
+        .iter()
+        .map(|(entity, storage, transform)| Supply {
+            entity,
+            position: transform.translation,
+            available: storage.current,
+        })
+        .collect();
+
+    // Blueprints come first since that's what the player is waiting on
+    let demands: Vec<Demand> = blueprint_query
+        .iter()
+        .map(|(entity, blueprint, transform)| Demand {
+            entity,
+            position: transform.translation,
+            missing: blueprint.cost.saturating_sub(blueprint.current),
+        })
+        .chain(segment_query.iter().map(|(entity, segment, pipe)| Demand {
+            entity,
+            position: pipe.midpoint(),
+            missing: segment.cost.saturating_sub(segment.current),
+        }))
+        .chain(
+            tank_query
+                .iter()
+                .map(|(entity, storage, transform)| Demand {
+                    entity,
+                    position: transform.translation,
+                    missing: storage.capacity - storage.current,
+                }),
+        )
+        .filter(|e| e.missing > 0)
+        .collect();
+
+    jobs.release_met(&demands);
+
+    let drones: Vec<IdleDrone> = drone_query
+        .iter()
+        .filter(|(entity, _, _)| !jobs.has_job(*entity))
+        .map(|(entity, drone, transform)| IdleDrone {
+            entity,
+            position: transform.translation,
+            carrying: drone.carrying,
+        })
+        .collect();
+
+    if drones.is_empty() {
+        return;
+    }
+
+    jobs.assign(
+        settings.drone_carry,
+        &drones,
+        &supplies,
+        &demands,
+        |a, b| networks.connected(a, b),
+    );
+}
+
+/// Flies every drone towards its source, target or home and does the pick up or drop off once it gets there
+pub fn fly_drones(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    settings: Res<GameSettings>,
+    mut jobs: ResMut<DroneJobs>,
+
+    mut drone_query: Query<(Entity, &mut Drone, &mut Transform)>,
+    building_query: Query<(&Transform, Option<&Pipe>), Without<Drone>>,
+    mut storage_query: Query<&mut IridiumStorage>,
+    mut blueprint_query: Query<&mut PlacedBlueprint>,
+    mut segment_query: Query<&mut SegmentBlueprint>,
+) {
+    for (entity, mut drone, mut transform) in drone_query.iter_mut() {
+        let job = jobs.get(entity);
+
+        let destination = match job {
+            Some(job) => job.source.unwrap_or(job.target),
+            None => drone.home,
+        };
+
+        let hover_point = match building_query.get(destination) {
+            // Two-point buildings have their root at the origin, their ends say where they are
+            Ok((_, Some(pipe))) => pipe.midpoint() + HOVER_OFFSET,
+            Ok((transform, None)) => transform.translation + HOVER_OFFSET,
+            Err(_) => {
+                // Whatever it was flying to is gone, so wait for a new job where it is
+                jobs.finish(entity);
+                drone.velocity = Vec3::ZERO;
+                continue;
+            }
+        };
+
+        let desired = steer(
+            &rapier_context,
+            &settings,
+            transform.translation,
+            hover_point,
+        ) * settings.drone_speed;
+        drone.velocity = drone
+            .velocity
+            .lerp(desired, (time.delta_seconds() * 4.0).min(1.0));
+        transform.translation += drone.velocity * time.delta_seconds();
+
+        if transform.translation.distance(hover_point) > ARRIVE_DISTANCE {
+            continue;
+        }
+
+        let job = match job {
+            Some(e) => e,
+            None => continue,
+        };
+
+        if let Some(source) = job.source {
+            if let Ok(mut storage) = storage_query.get_mut(source) {
+                drone.carrying += storage.drain(settings.drone_carry);
+            }
+
+            match drone.carrying {
+                0 => jobs.finish(entity),
+                _ => jobs.picked_up(entity),
+            }
+            continue;
+        }
+
+        if let Ok(mut blueprint) = blueprint_query.get_mut(job.target) {
+            let given = drone
+                .carrying
+                .min(blueprint.cost.saturating_sub(blueprint.current));
+            blueprint.current += given;
+            drone.carrying -= given;
+        } else if let Ok(mut segment) = segment_query.get_mut(job.target) {
+            let given = drone
+                .carrying
+                .min(segment.cost.saturating_sub(segment.current));
+            segment.current += given;
+            drone.carrying -= given;
+        } else if let Ok(mut storage) = storage_query.get_mut(job.target) {
+            drone.carrying -= storage.fill(drone.carrying);
+        }
+
+        // Anything that didn't fit stays on the drone for its next job
+        jobs.finish(entity);
+    }
+}
+
+/// Direction a drone at `position` should fly in to get to `destination`
+///
+/// Keeps the drone above the terrain while cruising and turns (or climbs) away from terrain in front of it
+fn steer(
+    rapier_context: &RapierContext,
+    settings: &GameSettings,
+    position: Vec3,
+    destination: Vec3,
+) -> Vec3 {
+    let to_destination = destination - position;
+    let distance = to_destination.length();
+    if distance < 0.01 {
+        return Vec3::ZERO;
+    }
+
+    let mut direction = to_destination / distance;
+
+    if Vec2::new(to_destination.x, to_destination.z).length() > settings.drone_lookahead {
+        if let Some((_, toi)) = rapier_context.cast_ray(
+            position,
+            -Vec3::Y,
+            settings.drone_cruise_height,
+            true,
+            DRONE_RAY,
+            None,
+        ) {
+            direction.y = direction.y.max(1.0 - toi / settings.drone_cruise_height);
+            direction = direction.normalize();
+        }
+    }
+
+    let lookahead = settings.drone_lookahead.min(distance);
+    let blocked = |direction: Vec3| {
+        rapier_context
+            .cast_ray(position, direction, lookahead, true, DRONE_RAY, None)
+            .is_some()
+    };
+
+    if blocked(direction) {
+        direction = [FRAC_PI_4, -FRAC_PI_4, FRAC_PI_2, -FRAC_PI_2]
+            .iter()
+            .map(|angle| Quat::from_rotation_y(*angle) * direction)
+            .find(|e| !blocked(*e))
+            .unwrap_or(Vec3::Y);
+    }
+
+    direction
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, buildings::BuildingType, raycasting::BuildCursor,
+    },
+    collision_layers::{CollisionLayer, CollisionLayers, Layers},
+    constants::{GARAGE_PAD_OFFSET, PLAYER_COLLISION, VEHICLE_HALF_EXTENTS},
+    iridium_system::{network::IridiumNetworks, storage::IridiumStorage},
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput},
+        interaction::Interactable,
+        vehicle::Vehicle,
+    },
+    settings::GameSettings,
+};
+
+/// A finished garage
+#[derive(Component)]
+pub struct Garage;
+
+pub struct VehicleAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub fn garage_startup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(VehicleAssets {
+        mesh: meshes.add(Mesh::from(shape::Box::new(
+            VEHICLE_HALF_EXTENTS.x * 2.0,
+            VEHICLE_HALF_EXTENTS.y * 2.0,
+            VEHICLE_HALF_EXTENTS.z * 2.0,
+        ))),
+        material: materials.add(Color::rgb(0.35, 0.4, 0.3).into()),
+    });
+}
+
+pub fn init_garages(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type == BuildingType::Garage {
+            commands.entity(event.entity).insert(Garage);
+        }
+    }
+}
+
+/// With the cursor over a garage, F makes a vehicle on its pad
+///
+/// Nothing is made (or paid for) if the pad is blocked or the garage's network can't afford it
+pub fn garage_input(
+    mut commands: Commands,
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+    networks: Res<IridiumNetworks>,
+    rapier_context: Res<RapierContext>,
+    vehicle_assets: Res<VehicleAssets>,
+    settings: Res<GameSettings>,
+
+    parent_query: Query<&Parent>,
+    garage_query: Query<&Transform, With<Garage>>,
+    mut storage_query: Query<&mut IridiumStorage>,
+) {
+    if selected_building.id.is_some() || !input.just_pressed(Action::UseBuilding) {
+        return;
+    }
+
+    let (garage, transform) = match build_cursor
+        .hovered_entity(&parent_query)
+        .and_then(|e| garage_query.get(e).ok().map(|t| (e, t)))
+    {
+        Some(e) => e,
+        None => return,
+    };
+
+    let pad = transform.translation + transform.rotation * *GARAGE_PAD_OFFSET;
+    let collider = Collider::cuboid(
+        VEHICLE_HALF_EXTENTS.x,
+        VEHICLE_HALF_EXTENTS.y,
+        VEHICLE_HALF_EXTENTS.z,
+    );
+
+    // Check against the terrain, players, vehicles and finished buildings, but not shield domes
+    let blocked = rapier_context.intersection_with_shape(
+        pad,
+        transform.rotation,
+        &collider,
+        CollisionLayers::new(
+            Layers::of(&[CollisionLayer::Building]),
+            Layers::of(&[
+                CollisionLayer::Terrain,
+                CollisionLayer::Player,
+                CollisionLayer::Building,
+            ]),
+        )
+        .interaction_groups(),
+        None,
+    );
+    if blocked.is_some() {
+        info!("garage pad is blocked");
+        return;
+    }
+
+    if networks.available(&storage_query, garage) < settings.vehicle_cost {
+        info!("not enough iridium to make a vehicle");
+        return;
+    }
+    networks.withdraw(&mut storage_query, garage, settings.vehicle_cost);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: vehicle_assets.mesh.clone(),
+            material: vehicle_assets.material.clone(),
+            transform: Transform::from_translation(pad).with_rotation(transform.rotation),
+            ..Default::default()
+        })
+        .insert(Vehicle {
+            speed: settings.vehicle_speed,
+        })
+        .insert(Interactable::new("prompt.drive"))
+        .insert(collider)
+        .insert(Friction {
+            coefficient: 0.2,
+            combine_rule: CoefficientCombineRule::Min,
+        })
+        .insert(Velocity::default())
+        .insert(RigidBody::Dynamic)
+        .insert(LockedAxes::ROTATION_LOCKED_X | LockedAxes::ROTATION_LOCKED_Z)
+        .insert(*PLAYER_COLLISION);
+}
@@ -0,0 +1,334 @@
+use bevy::{math::Vec3, prelude::Entity, utils::HashMap};
+
+/// A building drones can pick iridium up from
+pub struct Supply {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub available: u32,
+}
+
+/// A blueprint or tank that wants iridium
+pub struct Demand {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub missing: u32,
+}
+
+/// A drone that can be given a job
+pub struct IdleDrone {
+    pub entity: Entity,
+    pub position: Vec3,
+    /// Iridium left over from a trip whose target went away
+    pub carrying: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DroneJob {
+    /// `None` once the drone has picked up its iridium (or if it already had some)
+    pub source: Option<Entity>,
+    pub target: Entity,
+}
+
+/// Which drone is doing which trip
+///
+/// Doesn't touch the ECS so the assignment can be tested on its own, see the tests at the bottom
+#[derive(Default)]
+pub struct DroneJobs {
+    jobs: HashMap<Entity, DroneJob>,
+}
+
+impl DroneJobs {
+    pub fn get(&self, drone: Entity) -> Option<DroneJob> {
+        self.jobs.get(&drone).copied()
+    }
+
+    pub fn has_job(&self, drone: Entity) -> bool {
+        self.jobs.contains_key(&drone)
+    }
+
+    /// Call once the drone has taken its iridium from the source
+    pub fn picked_up(&mut self, drone: Entity) {
+        if let Some(job) = self.jobs.get_mut(&drone) {
+            job.source = None;
+        }
+    }
+
+    pub fn finish(&mut self, drone: Entity) {
+        self.jobs.remove(&drone);
+    }
+
+    /// Drops the jobs of drones that don't exist anymore
+    pub fn retain(&mut self, mut exists: impl FnMut(Entity) -> bool) {
+        self.jobs.retain(|drone, _| exists(*drone));
+    }
+
+    /// Drops the jobs whose target isn't in `demands` anymore (finished blueprints, full tanks), drones
+    /// that already picked up their iridium keep it for their next job
+    pub fn release_met(&mut self, demands: &[Demand]) {
+        self.jobs
+            .retain(|_, job| demands.iter().any(|e| e.entity == job.target));
+    }
+
+    /// Gives idle drones trips until every demand is covered or there are no drones/supplies left
+    ///
+    /// Demands are served in order, each by the closest drone that can reach it. Drones that are
+    /// already carrying iridium go straight to the target, empty ones go through the supply closest
+    /// to them that isn't in the same network as the target (`same_network`)
+    pub fn assign(
+        &mut self,
+        carry: u32,
+        drones: &[IdleDrone],
+        supplies: &[Supply],
+        demands: &[Demand],
+        same_network: impl Fn(Entity, Entity) -> bool,
+    ) {
+        let mut inbound: HashMap<Entity, u32> = HashMap::default();
+        let mut reserved: HashMap<Entity, u32> = HashMap::default();
+        for job in self.jobs.values() {
+            *inbound.entry(job.target).or_default() += carry;
+            if let Some(source) = job.source {
+                *reserved.entry(source).or_default() += carry;
+            }
+        }
+
+        let mut free: Vec<&IdleDrone> = drones
+            .iter()
+            .filter(|e| !self.jobs.contains_key(&e.entity))
+            .collect();
+
+        for demand in demands {
+            while inbound.get(&demand.entity).copied().unwrap_or(0) < demand.missing {
+                // Loaded drones don't need a supply, so use the closest one of those first
+                let loaded = closest(&free, demand.position, |e| e.carrying > 0);
+
+                let (index, job, amount) = match loaded {
+                    Some(index) => (
+                        index,
+                        DroneJob {
+                            source: None,
+                            target: demand.entity,
+                        },
+                        free[index].carrying,
+                    ),
+                    None => {
+                        let index = match closest(&free, demand.position, |_| true) {
+                            Some(e) => e,
+                            None => break,
+                        };
+
+                        let source = supplies
+                            .iter()
+                            .filter(|e| {
+                                e.available
+                                    .saturating_sub(reserved.get(&e.entity).copied().unwrap_or(0))
+                                    >= carry
+                                    && !same_network(e.entity, demand.entity)
+                            })
+                            .min_by(|a, b| {
+                                let position = free[index].position;
+                                a.position
+                                    .distance_squared(position)
+                                    .partial_cmp(&b.position.distance_squared(position))
+                                    .unwrap()
+                            });
+
+                        let source = match source {
+                            Some(e) => e.entity,
+                            None => break,
+                        };
+                        *reserved.entry(source).or_default() += carry;
+
+                        (
+                            index,
+                            DroneJob {
+                                source: Some(source),
+                                target: demand.entity,
+                            },
+                            carry,
+                        )
+                    }
+                };
+
+                *inbound.entry(demand.entity).or_default() += amount;
+                self.jobs.insert(free[index].entity, job);
+                free.swap_remove(index);
+            }
+        }
+    }
+}
+
+/// Index of the drone in `drones` closest to `position` that matches `filter`
+fn closest(
+    drones: &[&IdleDrone],
+    position: Vec3,
+    filter: impl Fn(&IdleDrone) -> bool,
+) -> Option<usize> {
+    drones
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| filter(e))
+        .min_by(|(_, a), (_, b)| {
+            a.position
+                .distance_squared(position)
+                .partial_cmp(&b.position.distance_squared(position))
+                .unwrap()
+        })
+        .map(|(i, _)| i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CARRY: u32 = 10;
+
+    fn drone(id: u32, x: f32) -> IdleDrone {
+        IdleDrone {
+            entity: Entity::from_raw(id),
+            position: Vec3::new(x, 0.0, 0.0),
+            carrying: 0,
+        }
+    }
+
+    fn supply(id: u32, available: u32) -> Supply {
+        Supply {
+            entity: Entity::from_raw(id),
+            position: Vec3::ZERO,
+            available,
+        }
+    }
+
+    fn demand(id: u32, missing: u32) -> Demand {
+        Demand {
+            entity: Entity::from_raw(id),
+            position: Vec3::new(5.0, 0.0, 0.0),
+            missing,
+        }
+    }
+
+    #[test]
+    fn drones_dont_claim_the_same_blueprint() {
+        let mut jobs = DroneJobs::default();
+        let drones = [drone(1, 0.0), drone(2, 1.0)];
+
+        jobs.assign(
+            CARRY,
+            &drones,
+            &[supply(10, 100)],
+            &[demand(20, CARRY)],
+            |_, _| false,
+        );
+
+        // The closest drone covers it on its own
+        assert!(!jobs.has_job(Entity::from_raw(1)));
+        assert_eq!(
+            jobs.get(Entity::from_raw(2)),
+            Some(DroneJob {
+                source: Some(Entity::from_raw(10)),
+                target: Entity::from_raw(20),
+            })
+        );
+
+        // Assigning again doesn't send the other one too
+        jobs.assign(
+            CARRY,
+            &drones,
+            &[supply(10, 100)],
+            &[demand(20, CARRY)],
+            |_, _| false,
+        );
+        assert!(!jobs.has_job(Entity::from_raw(1)));
+    }
+
+    #[test]
+    fn big_blueprints_get_several_drones() {
+        let mut jobs = DroneJobs::default();
+        let drones = [drone(1, 0.0), drone(2, 1.0), drone(3, 2.0)];
+
+        jobs.assign(
+            CARRY,
+            &drones,
+            &[supply(10, 100)],
+            &[demand(20, CARRY * 2)],
+            |_, _| false,
+        );
+
+        let busy = drones.iter().filter(|e| jobs.has_job(e.entity)).count();
+        assert_eq!(busy, 2);
+    }
+
+    #[test]
+    fn finished_blueprints_release_their_job() {
+        let mut jobs = DroneJobs::default();
+        let drones = [drone(1, 0.0)];
+        let demands = [demand(20, CARRY)];
+
+        jobs.assign(CARRY, &drones, &[supply(10, 100)], &demands, |_, _| false);
+        jobs.release_met(&demands);
+        assert!(jobs.has_job(Entity::from_raw(1)));
+
+        jobs.release_met(&[]);
+        assert!(!jobs.has_job(Entity::from_raw(1)));
+    }
+
+    #[test]
+    fn supplies_arent_overdrawn() {
+        let mut jobs = DroneJobs::default();
+        let drones = [drone(1, 0.0), drone(2, 1.0)];
+        let demands = [demand(20, CARRY), demand(21, CARRY)];
+
+        // Only enough for one trip
+        jobs.assign(CARRY, &drones, &[supply(10, CARRY)], &demands, |_, _| false);
+        let busy = drones.iter().filter(|e| jobs.has_job(e.entity)).count();
+        assert_eq!(busy, 1);
+
+        // Not even one
+        let mut jobs = DroneJobs::default();
+        jobs.assign(
+            CARRY,
+            &drones,
+            &[supply(10, CARRY - 1)],
+            &demands,
+            |_, _| false,
+        );
+        assert!(drones.iter().all(|e| !jobs.has_job(e.entity)));
+    }
+
+    #[test]
+    fn supplies_in_the_same_network_are_skipped() {
+        let mut jobs = DroneJobs::default();
+
+        jobs.assign(
+            CARRY,
+            &[drone(1, 0.0)],
+            &[supply(10, 100)],
+            &[demand(20, CARRY)],
+            |_, _| true,
+        );
+        assert!(!jobs.has_job(Entity::from_raw(1)));
+    }
+
+    #[test]
+    fn loaded_drones_skip_the_supply() {
+        let mut jobs = DroneJobs::default();
+        let mut loaded = drone(1, 50.0);
+        loaded.carrying = CARRY;
+
+        jobs.assign(
+            CARRY,
+            &[drone(2, 0.0), loaded],
+            &[],
+            &[demand(20, CARRY)],
+            |_, _| false,
+        );
+
+        assert_eq!(
+            jobs.get(Entity::from_raw(1)),
+            Some(DroneJob {
+                source: None,
+                target: Entity::from_raw(20),
+            })
+        );
+        assert!(!jobs.has_job(Entity::from_raw(2)));
+    }
+}
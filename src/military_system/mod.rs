@@ -0,0 +1,65 @@
+use bevy::{
+    core::FixedTimestep,
+    prelude::{AddAsset, App, ParallelSystemDescriptorCoercion, Plugin, SystemSet},
+};
+
+use crate::building_system::health::{apply_damage, queue_damage};
+
+use self::{
+    arsenal::{
+        arsenal_input, arsenal_startup, init_arsenals, run_production, update_arsenal_panel,
+        RecipeTable, RecipeTableLoader,
+    },
+    drone::{assign_drone_jobs, fly_drones},
+    garage::{garage_input, garage_startup, init_garages},
+    logistics::DroneJobs,
+    shield::{
+        shield_absorb_damage, shield_startup, spawn_shield_domes, update_dome_coverage,
+        update_shield_state,
+    },
+    ssim::{init_ssims, ssim_input, ssim_startup},
+};
+
+pub mod arsenal;
+pub mod drone;
+pub mod garage;
+pub mod logistics;
+pub mod shield;
+pub mod ssim;
+
+pub struct MilitarySystemPlugin;
+
+impl Plugin for MilitarySystemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_asset::<RecipeTable>()
+            .init_asset_loader::<RecipeTableLoader>()
+            .add_startup_system(shield_startup)
+            .add_startup_system(arsenal_startup)
+            .add_system(spawn_shield_domes)
+            .add_system(update_dome_coverage.before(shield_absorb_damage))
+            // Both change `ShieldGenerator.active`, a shield that collapses this frame absorbs nothing
+            .add_system(update_shield_state.before(shield_absorb_damage))
+            .add_system(
+                shield_absorb_damage
+                    .after(queue_damage)
+                    .before(apply_damage),
+            )
+            .add_system(init_arsenals)
+            .add_system(arsenal_input)
+            .add_system(run_production)
+            .add_system(update_arsenal_panel)
+            .add_startup_system(garage_startup)
+            .add_system(init_garages)
+            .add_system(garage_input)
+            .insert_resource(DroneJobs::default())
+            .add_startup_system(ssim_startup)
+            .add_system(init_ssims)
+            .add_system(ssim_input)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(FixedTimestep::step(0.5))
+                    .with_system(assign_drone_jobs),
+            )
+            .add_system(fly_drones);
+    }
+}
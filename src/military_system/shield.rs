@@ -0,0 +1,327 @@
+use bevy::{pbr::NotShadowCaster, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, buildings::BuildingType, health::BuildingHealth,
+    },
+    collision_layers::{CollisionLayer, CollisionLayers},
+    iridium_system::{network::IridiumNetworks, storage::IridiumStorage},
+    settings::GameSettings,
+};
+
+/// A finished shield generator
+#[derive(Component)]
+pub struct ShieldGenerator {
+    /// `false` when the shield has collapsed
+    pub active: bool,
+}
+
+/// The dome a shield generator projects, it is a child of the generator
+#[derive(Component, Default)]
+pub struct ShieldDome {
+    /// Buildings with a collider inside the dome, kept up to date by `update_dome_coverage`
+    pub covered: Vec<Entity>,
+}
+
+pub struct ShieldAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub fn shield_startup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<GameSettings>,
+) {
+    commands.insert_resource(ShieldAssets {
+        mesh: meshes.add(Mesh::from(shape::Icosphere {
+            radius: settings.shield_radius,
+            subdivisions: 4,
+        })),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgba(87.0 / 255.0, 202.0 / 255.0, 1.0, 0.15),
+            emissive: Color::rgb(0.0, 0.3, 0.4),
+            alpha_mode: AlphaMode::Blend,
+            double_sided: true,
+            cull_mode: None,
+            ..Default::default()
+        }),
+    });
+}
+
+/// Gives finished shield generators their dome
+///
+/// Shields start collapsed and come up once their network has enough iridium
+pub fn spawn_shield_domes(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    shield_assets: Res<ShieldAssets>,
+    settings: Res<GameSettings>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::ShieldGenerator {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert(ShieldGenerator { active: false })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(PbrBundle {
+                        mesh: shield_assets.mesh.clone(),
+                        material: shield_assets.material.clone(),
+                        visibility: Visibility { is_visible: false },
+                        ..Default::default()
+                    })
+                    .insert_bundle((
+                        Collider::ball(settings.shield_radius),
+                        Sensor(true),
+                        ActiveCollisionTypes::all(),
+                        // Only finished buildings are in the building layer
+                        CollisionLayers::of(CollisionLayer::ShieldDome).groups(),
+                        NotShadowCaster,
+                        ShieldDome::default(),
+                    ));
+            });
+    }
+}
+
+/// Collapses shields whose network has run dry and restores them once it has refilled
+pub fn update_shield_state(
+    networks: Res<IridiumNetworks>,
+    settings: Res<GameSettings>,
+    storage_query: Query<&mut IridiumStorage>,
+
+    mut shield_query: Query<(Entity, &mut ShieldGenerator, &Children)>,
+    mut dome_query: Query<&mut Visibility, With<ShieldDome>>,
+) {
+    for (entity, mut shield, children) in shield_query.iter_mut() {
+        let available = networks.available(&storage_query, entity);
+
+        let active = match shield.active {
+            true => available > 0,
+            false => available >= settings.shield_restart_iridium,
+        };
+
+        if active != shield.active {
+            set_shield_active(&mut shield, children, &mut dome_query, active);
+        }
+    }
+}
+
+/// Finds the buildings inside every dome
+pub fn update_dome_coverage(
+    rapier_context: Res<RapierContext>,
+
+    mut dome_query: Query<(Entity, &mut ShieldDome)>,
+    parent_query: Query<&Parent>,
+) {
+    for (dome, mut shield_dome) in dome_query.iter_mut() {
+        shield_dome.covered.clear();
+
+        for (a, b, intersecting) in rapier_context.intersections_with(dome) {
+            if !intersecting {
+                continue;
+            }
+
+            // The collider is a child of the actual building
+            let other = if a == dome { b } else { a };
+            let building = parent_query.get(other).map_or(other, |e| e.0);
+
+            if !shield_dome.covered.contains(&building) {
+                shield_dome.covered.push(building);
+            }
+        }
+    }
+}
+
+/// Absorbs pending damage dealt to buildings inside active domes, paid for with iridium from the generator's network
+///
+/// Damage absorbed by one shield is removed from the building's pending damage, so overlapping shields never absorb the same damage twice
+pub fn shield_absorb_damage(
+    networks: Res<IridiumNetworks>,
+    settings: Res<GameSettings>,
+
+    mut shield_query: Query<(Entity, &mut ShieldGenerator, &Children)>,
+    coverage_query: Query<&ShieldDome>,
+    mut dome_query: Query<&mut Visibility, With<ShieldDome>>,
+    mut health_query: Query<&mut BuildingHealth>,
+    mut storage_query: Query<&mut IridiumStorage>,
+) {
+    let per_damage = settings.shield_iridium_per_damage;
+
+    for (shield_entity, mut shield, children) in shield_query.iter_mut() {
+        if !shield.active {
+            continue;
+        }
+
+        let covered = match children.iter().find_map(|e| coverage_query.get(*e).ok()) {
+            Some(e) => &e.covered,
+            None => continue,
+        };
+
+        for building in covered.iter() {
+            let mut health = match health_query.get_mut(*building) {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            if health.pending == 0 {
+                continue;
+            }
+
+            // Only whole points of damage are paid for, so no iridium is lost to rounding
+            let affordable = networks
+                .available(&storage_query, shield_entity)
+                .checked_div(per_damage)
+                .unwrap_or(u32::MAX);
+            let absorbed = health.pending.min(affordable);
+            networks.withdraw(&mut storage_query, shield_entity, absorbed * per_damage);
+            health.pending -= absorbed;
+
+            if health.pending > 0 {
+                set_shield_active(&mut shield, children, &mut dome_query, false);
+                break;
+            }
+        }
+    }
+}
+
+fn set_shield_active(
+    shield: &mut ShieldGenerator,
+    children: &Children,
+    dome_query: &mut Query<&mut Visibility, With<ShieldDome>>,
+    active: bool,
+) {
+    shield.active = active;
+
+    for child in children.iter() {
+        if let Ok(mut visibility) = dome_query.get_mut(*child) {
+            visibility.is_visible = active;
+        }
+    }
+
+    match active {
+        true => info!("shield is up"),
+        false => info!("shield collapsed"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+
+    use crate::building_system::health::{apply_damage, queue_damage, DamageEvent};
+
+    use super::*;
+
+    const PER_DAMAGE: u32 = 5;
+
+    /// Damage goes through the same systems as in the game, the domes already know what they cover
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .insert_resource(GameSettings {
+                shield_iridium_per_damage: PER_DAMAGE,
+                ..default()
+            })
+            // Nothing is piped together, every shield pays from its own storage
+            .insert_resource(IridiumNetworks::default())
+            .add_event::<DamageEvent>()
+            .add_system(queue_damage)
+            .add_system(shield_absorb_damage.after(queue_damage))
+            .add_system(apply_damage.after(shield_absorb_damage));
+        app
+    }
+
+    fn spawn_shield(app: &mut App, iridium: u32, covered: Vec<Entity>) -> Entity {
+        app.world
+            .spawn()
+            .insert_bundle((
+                ShieldGenerator { active: true },
+                IridiumStorage {
+                    current: iridium,
+                    capacity: 1000,
+                },
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn()
+                    .insert_bundle((ShieldDome { covered }, Visibility { is_visible: true }));
+            })
+            .id()
+    }
+
+    fn damage(app: &mut App, target: Entity, amount: u32) {
+        app.world
+            .get_resource_mut::<Events<DamageEvent>>()
+            .unwrap()
+            .send(DamageEvent { target, amount });
+        app.update();
+    }
+
+    fn iridium(app: &App, shield: Entity) -> u32 {
+        app.world.get::<IridiumStorage>(shield).unwrap().current
+    }
+
+    #[test]
+    fn overlapping_shields_absorb_once() {
+        let mut app = app();
+        let building = app.world.spawn().insert(BuildingHealth::new(100)).id();
+        let first = spawn_shield(&mut app, 500, vec![building]);
+        let second = spawn_shield(&mut app, 500, vec![building]);
+
+        damage(&mut app, building, 10);
+
+        assert_eq!(
+            app.world.get::<BuildingHealth>(building).unwrap().current,
+            100
+        );
+        // Only one of them paid for it
+        assert_eq!(
+            iridium(&app, first) + iridium(&app, second),
+            1000 - 10 * PER_DAMAGE
+        );
+        assert!(app.world.get::<ShieldGenerator>(first).unwrap().active);
+        assert!(app.world.get::<ShieldGenerator>(second).unwrap().active);
+    }
+
+    #[test]
+    fn shields_that_run_dry_collapse_and_let_the_rest_through() {
+        let mut app = app();
+        let building = app.world.spawn().insert(BuildingHealth::new(100)).id();
+        // Enough for 4 points of damage each, with 2 left over
+        let first = spawn_shield(&mut app, 22, vec![building]);
+        let second = spawn_shield(&mut app, 22, vec![building]);
+
+        damage(&mut app, building, 10);
+
+        assert_eq!(
+            app.world.get::<BuildingHealth>(building).unwrap().current,
+            98
+        );
+        // What couldn't pay for a whole point is kept
+        assert_eq!(iridium(&app, first), 2);
+        assert_eq!(iridium(&app, second), 2);
+        assert!(!app.world.get::<ShieldGenerator>(first).unwrap().active);
+        assert!(!app.world.get::<ShieldGenerator>(second).unwrap().active);
+    }
+
+    #[test]
+    fn uncovered_buildings_take_the_damage() {
+        let mut app = app();
+        let building = app.world.spawn().insert(BuildingHealth::new(100)).id();
+        let shield = spawn_shield(&mut app, 500, Vec::new());
+
+        damage(&mut app, building, 10);
+
+        assert_eq!(
+            app.world.get::<BuildingHealth>(building).unwrap().current,
+            90
+        );
+        assert_eq!(iridium(&app, shield), 500);
+    }
+}
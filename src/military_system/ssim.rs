@@ -0,0 +1,101 @@
+use bevy::{math::const_vec3, prelude::*};
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, buildings::BuildingType, raycasting::BuildCursor,
+    },
+    iridium_system::{network::IridiumNetworks, storage::IridiumStorage},
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput},
+    },
+    settings::GameSettings,
+};
+
+use super::drone::Drone;
+
+/// Where new drones appear, relative to the S.S.I.M.
+const LAUNCH_OFFSET: Vec3 = const_vec3!([0.0, 1.6, 0.0]);
+
+/// A finished S.S.I.M.
+#[derive(Component)]
+pub struct Ssim;
+
+pub struct DroneAssets {
+    mesh: Handle<Mesh>,
+    material: Handle<StandardMaterial>,
+}
+
+pub fn ssim_startup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(DroneAssets {
+        mesh: meshes.add(Mesh::from(shape::Box::new(0.6, 0.2, 0.6))),
+        material: materials.add(StandardMaterial {
+            base_color: Color::rgb(0.3, 0.3, 0.35),
+            emissive: Color::rgb(0.1, 0.4, 0.6),
+            ..Default::default()
+        }),
+    });
+}
+
+pub fn init_ssims(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type == BuildingType::Ssim {
+            commands.entity(event.entity).insert(Ssim);
+        }
+    }
+}
+
+/// With the cursor over a S.S.I.M., F makes a drone if it has room for one and its network can afford it
+pub fn ssim_input(
+    mut commands: Commands,
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+    networks: Res<IridiumNetworks>,
+    drone_assets: Res<DroneAssets>,
+    settings: Res<GameSettings>,
+
+    parent_query: Query<&Parent>,
+    ssim_query: Query<&Transform, With<Ssim>>,
+    drone_query: Query<&Drone>,
+    mut storage_query: Query<&mut IridiumStorage>,
+) {
+    if selected_building.id.is_some() || !input.just_pressed(Action::UseBuilding) {
+        return;
+    }
+
+    let (ssim, transform) = match build_cursor
+        .hovered_entity(&parent_query)
+        .and_then(|e| ssim_query.get(e).ok().map(|t| (e, t)))
+    {
+        Some(e) => e,
+        None => return,
+    };
+
+    if drone_query.iter().filter(|e| e.home == ssim).count() >= settings.drones_per_ssim {
+        info!("S.S.I.M. already has {} drones", settings.drones_per_ssim);
+        return;
+    }
+
+    if networks.available(&storage_query, ssim) < settings.drone_cost {
+        info!("not enough iridium to make a drone");
+        return;
+    }
+    networks.withdraw(&mut storage_query, ssim, settings.drone_cost);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: drone_assets.mesh.clone(),
+            material: drone_assets.material.clone(),
+            transform: Transform::from_translation(transform.translation + LAUNCH_OFFSET),
+            ..Default::default()
+        })
+        .insert(Drone::new(ssim));
+}
@@ -0,0 +1,317 @@
+use anyhow::{anyhow, bail};
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadedAsset},
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap, HashSet},
+};
+use serde::Deserialize;
+
+use crate::{
+    building_system::buildings::{string_to_building_enum, BuildingsResource},
+    localization::{Localization, StringTable},
+};
+
+use super::{gui::GuiOr, gui_startup::GuiSelectedBranch};
+
+/// Where (in `assets`) the menu tree is loaded from at startup
+pub const BUILD_MENU_PATH: &str = "data/build.menu.ron";
+
+/// Id of the branch the menu starts in, going back from it stays in it
+pub const BUILD_MENU_ROOT: &str = "base";
+
+/// How many buttons the menu has, so how many entries a branch can have
+pub const BUILD_MENU_BUTTONS: usize = 4;
+
+/// A branch as it's written in the menu file, branches are nested in their parent
+///
+/// Labels come from the string table, see `label_key`
+#[derive(Deserialize, Debug)]
+pub struct BranchDefinition {
+    pub id: String,
+    pub children: Vec<EntryDefinition>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ItemDefinition {
+    pub id: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum EntryDefinition {
+    Branch(BranchDefinition),
+    Item(ItemDefinition),
+}
+
+#[derive(Clone)]
+pub struct MenuBranch {
+    /// The branch Q goes back to
+    pub parent: String,
+    pub entries: [GuiOr; BUILD_MENU_BUTTONS],
+}
+
+/// The build menu tree, flattened so branches can be looked up by id
+///
+/// The resource is empty until `apply_build_menu` copies the loaded file into it
+#[derive(TypeUuid, Clone, Default)]
+#[uuid = "9d41c6f2-3b7e-4a58-8e1f-6a2c5d0b7e94"]
+pub struct BuildMenu {
+    branches: HashMap<String, MenuBranch>,
+    /// Items that have a building, `None` until the buildings are loaded
+    available: Option<HashSet<String>>,
+}
+
+impl BuildMenu {
+    /// Flattens the tree and checks that every id is unique and every branch fits on the buttons
+    pub fn from_definition(root: BranchDefinition) -> Result<Self, Vec<String>> {
+        let mut menu = BuildMenu {
+            branches: HashMap::default(),
+            available: None,
+        };
+        let mut errors = Vec::new();
+        let mut ids = HashSet::default();
+
+        if root.id != BUILD_MENU_ROOT {
+            errors.push(format!(
+                "root branch is \"{}\", it has to be \"{}\"",
+                root.id, BUILD_MENU_ROOT
+            ));
+        }
+
+        menu.add_branch(root, BUILD_MENU_ROOT, &mut ids, &mut errors);
+
+        match errors.is_empty() {
+            true => Ok(menu),
+            false => Err(errors),
+        }
+    }
+
+    fn add_branch(
+        &mut self,
+        branch: BranchDefinition,
+        parent: &str,
+        ids: &mut HashSet<String>,
+        errors: &mut Vec<String>,
+    ) {
+        Self::check_entry(&branch.id, parent, ids, errors);
+
+        if branch.children.is_empty() {
+            errors.push(format!("branch \"{}\" is empty", branch.id));
+        }
+        if branch.children.len() > BUILD_MENU_BUTTONS {
+            errors.push(format!(
+                "branch \"{}\" has {} entries, at most {} fit",
+                branch.id,
+                branch.children.len(),
+                BUILD_MENU_BUTTONS
+            ));
+        }
+
+        let mut entries = [GuiOr::None, GuiOr::None, GuiOr::None, GuiOr::None];
+        let mut sub_branches = Vec::new();
+
+        for (i, child) in branch.children.into_iter().enumerate() {
+            let entry = match child {
+                EntryDefinition::Branch(e) => {
+                    let entry = GuiOr::Id(e.id.clone());
+                    sub_branches.push(e);
+                    entry
+                }
+                EntryDefinition::Item(e) => {
+                    Self::check_entry(&e.id, &branch.id, ids, errors);
+                    GuiOr::Item(e.id)
+                }
+            };
+
+            if let Some(slot) = entries.get_mut(i) {
+                *slot = entry;
+            }
+        }
+
+        self.branches.insert(
+            branch.id.clone(),
+            MenuBranch {
+                parent: parent.to_string(),
+                entries,
+            },
+        );
+
+        for sub_branch in sub_branches {
+            self.add_branch(sub_branch, &branch.id, ids, errors);
+        }
+    }
+
+    fn check_entry(id: &str, parent: &str, ids: &mut HashSet<String>, errors: &mut Vec<String>) {
+        if id.is_empty() {
+            errors.push(format!("an entry in \"{}\" has no id", parent));
+        }
+        if !ids.insert(id.to_string()) {
+            errors.push(format!("id \"{}\" is used more than once", id));
+        }
+    }
+
+    pub fn branch(&self, id: &str) -> Option<&MenuBranch> {
+        self.branches.get(id)
+    }
+
+    /// The branch above `id`, the root is its own parent
+    pub fn parent(&self, id: &str) -> &str {
+        self.branches
+            .get(id)
+            .map_or(BUILD_MENU_ROOT, |e| e.parent.as_str())
+    }
+
+    /// Keys of the labels of branches and items that aren't in the string table, they show the key
+    pub fn missing_labels(&self, localization: &Localization) -> Vec<String> {
+        let mut missing: Vec<String> = self
+            .branches
+            .keys()
+            .map(|e| GuiOr::Id(e.clone()))
+            .chain(self.items().map(|e| GuiOr::Item(e.clone())))
+            .filter_map(|e| label_key(&e))
+            .filter(|e| !localization.has(e))
+            .collect();
+        missing.sort();
+        missing
+    }
+
+    /// Ids of every item in the menu
+    pub fn items(&self) -> impl Iterator<Item = &String> {
+        self.branches
+            .values()
+            .flat_map(|e| e.entries.iter())
+            .filter_map(|e| match e {
+                GuiOr::Item(id) => Some(id),
+                _ => None,
+            })
+    }
+
+    /// Finds the building of every item, returns the ids of items that don't have one
+    pub fn resolve(&mut self, buildings: &BuildingsResource) -> Vec<String> {
+        let (available, mut missing): (Vec<String>, Vec<String>) =
+            self.items().cloned().partition(|id| {
+                string_to_building_enum(id.clone()).map_or(false, |e| buildings.0.contains_key(&e))
+            });
+        missing.sort();
+
+        self.available = Some(available.into_iter().collect());
+        missing
+    }
+
+    pub fn resolved(&self) -> bool {
+        self.available.is_some()
+    }
+
+    /// Branches are always available, items only once they're resolved to a building
+    pub fn is_available(&self, entry: &GuiOr) -> bool {
+        match entry {
+            GuiOr::Id(_) => true,
+            GuiOr::Item(id) => self.available.as_ref().map_or(false, |e| e.contains(id)),
+            GuiOr::None => false,
+        }
+    }
+}
+
+/// Reads and validates `*.menu.ron` files, the error has every problem found if it's invalid
+#[derive(Default)]
+pub struct BuildMenuLoader;
+
+impl AssetLoader for BuildMenuLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let root: BranchDefinition = ron::de::from_bytes(bytes)
+                .map_err(|e| anyhow!("{:?}: {}", load_context.path(), e))?;
+
+            let menu = match BuildMenu::from_definition(root) {
+                Ok(e) => e,
+                Err(errors) => bail!(
+                    "{:?}: invalid build menu\n{}",
+                    load_context.path(),
+                    errors.join("\n")
+                ),
+            };
+
+            load_context.set_default_asset(LoadedAsset::new(menu));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["menu.ron"]
+    }
+}
+
+pub struct BuildMenuHandle(pub Handle<BuildMenu>);
+
+/// Copies the menu file into the `BuildMenu` resource once it's loaded (and again when it's edited)
+/// and goes back to the root branch, the buttons are refreshed by `gui`
+///
+/// A file that doesn't parse or validate is logged by the loader, the menu stays as it was
+pub fn apply_build_menu(
+    mut menu_events: EventReader<AssetEvent<BuildMenu>>,
+    menus: Res<Assets<BuildMenu>>,
+    handle: Res<BuildMenuHandle>,
+    mut build_menu: ResMut<BuildMenu>,
+    mut selected_branch: ResMut<GuiSelectedBranch>,
+) {
+    for event in menu_events.iter() {
+        let loaded = match event {
+            AssetEvent::Created { handle: e } | AssetEvent::Modified { handle: e } => e,
+            AssetEvent::Removed { .. } => continue,
+        };
+        if *loaded != handle.0 {
+            continue;
+        }
+        let menu = match menus.get(loaded) {
+            Some(e) => e,
+            None => continue,
+        };
+
+        // Items get resolved again by `resolve_menu_items`
+        *build_menu = menu.clone();
+        selected_branch.id = BUILD_MENU_ROOT.to_string();
+    }
+}
+
+/// Warns about menu labels that aren't in the string table, once the menu and the string tables are
+/// loaded (and again when either is edited)
+pub fn warn_missing_menu_labels(
+    mut menu_events: EventReader<AssetEvent<BuildMenu>>,
+    mut table_events: EventReader<AssetEvent<StringTable>>,
+    build_menu: Res<BuildMenu>,
+    localization: Res<Localization>,
+) {
+    // Both are read every frame so old events don't count later
+    let loaded = menu_events.iter().count() + table_events.iter().count() > 0;
+    if !loaded || build_menu.branch(BUILD_MENU_ROOT).is_none() || !localization.is_loaded() {
+        return;
+    }
+
+    let missing_labels = build_menu.missing_labels(&localization);
+    if !missing_labels.is_empty() {
+        warn!(
+            "{} build menu labels aren't in the string table: {}",
+            missing_labels.len(),
+            missing_labels.join(", ")
+        );
+    }
+}
+
+/// What's shown on the button of a branch or item, in the selected language
+pub fn label(entry: &GuiOr, localization: &Localization) -> String {
+    label_key(entry).map_or(String::new(), |e| localization.get(&e).to_string())
+}
+
+/// String table key of the label of a branch (`menu.<id>`) or item (`building.<id>`, items are named
+/// after their building)
+pub fn label_key(entry: &GuiOr) -> Option<String> {
+    match entry {
+        GuiOr::Id(id) => Some(format!("menu.{}", id)),
+        GuiOr::Item(id) => Some(format!("building.{}", id)),
+        GuiOr::None => None,
+    }
+}
@@ -1,9 +1,13 @@
 use crate::{
-    building_system::buildings::string_to_building_enum,
-    constants::{GUI_BACK_LOOKUP, GUI_LOOKUP},
+    building_system::buildings::{string_to_building_enum, BuildingsResource},
+    localization::Localization,
+    player_system::input::{Action, ActionInput},
 };
 
-use super::gui_startup::*;
+use super::{
+    build_menu::{label, BuildMenu, BUILD_MENU_BUTTONS},
+    gui_startup::*,
+};
 use bevy::{
     ecs::query::{EntityFetch, QueryIter, ReadFetch, WriteFetch},
     prelude::*,
@@ -19,9 +23,10 @@ pub fn gui(
     mut visibility_query: Query<&mut Visibility>,
     mut selected_branch: ResMut<GuiSelectedBranch>,
 
-    mut prev_q: ResMut<PrevQPress>,
-    keyboard_input: Res<Input<KeyCode>>,
+    input: ActionInput,
     mut selected_building: ResMut<SelectedBuilding>,
+    build_menu: Res<BuildMenu>,
+    localization: Res<Localization>,
 ) {
     selected_building.changed = false;
     let mut clicked = false;
@@ -46,10 +51,18 @@ pub fn gui(
                     &mut button_iter,
                     &mut visibility_query,
                     &mut selected_building,
+                    &build_menu,
+                    &localization,
                 );
             }
+            // Unavailable items don't light up
             Interaction::Hovered => {
-                *color = HOVERED_BUTTON.into();
+                let button_query_q1 = button_query.p1();
+                let hovered_button = button_query_q1.iter().nth(button_id.id as usize).unwrap();
+                *color = match build_menu.is_available(&hovered_button.content) {
+                    true => HOVERED_BUTTON.into(),
+                    false => NORMAL_BUTTON.into(),
+                };
             }
             Interaction::None => {
                 *color = NORMAL_BUTTON.into();
@@ -58,22 +71,17 @@ pub fn gui(
     }
 
     if !clicked {
-        let mut pressed_id = -1;
-        if keyboard_input.just_pressed(KeyCode::Z) {
-            pressed_id = 3;
-        } else if keyboard_input.just_pressed(KeyCode::X) {
-            pressed_id = 2;
-        } else if keyboard_input.just_pressed(KeyCode::C) {
-            pressed_id = 1;
-        } else if keyboard_input.just_pressed(KeyCode::V) {
-            pressed_id = 0;
-        }
+        let pressed_slot = Action::MENU_SLOTS
+            .iter()
+            .position(|e| input.just_pressed(*e));
 
-        if pressed_id >= 0 {
+        if let Some(slot) = pressed_slot {
+            // The first slot is the last button
+            let pressed_id = BUILD_MENU_BUTTONS - 1 - slot;
             let clicked_button_content = &button_query
                 .p1()
                 .iter()
-                .nth(pressed_id as usize)
+                .nth(pressed_id)
                 .unwrap()
                 .content
                 .clone();
@@ -86,31 +94,47 @@ pub fn gui(
                 &mut button_iter,
                 &mut visibility_query,
                 &mut selected_building,
+                &build_menu,
+                &localization,
             );
         }
     }
 
-    if keyboard_input.pressed(KeyCode::Q) && !prev_q.pressed {
+    if input.just_pressed(Action::MenuBack) {
         let mut button_query_q0 = button_query.p0();
         let mut button_iter = button_query_q0.iter_mut();
-        selected_branch.id = GUI_BACK_LOOKUP
-            .get(&selected_branch.id)
-            .unwrap()
-            .to_string();
+        selected_branch.id = build_menu.parent(&selected_branch.id).to_string();
         change_buttons(
             &selected_branch.id,
             &mut button_iter,
             &mut text_query,
             &mut visibility_query,
+            &build_menu,
+            &localization,
+        );
+    }
+
+    // Items were just resolved (grey out the ones already on screen) or the string tables just loaded
+    if (build_menu.is_changed() && !build_menu.is_added())
+        || (localization.is_changed() && !localization.is_added())
+    {
+        let mut button_query_q0 = button_query.p0();
+        let mut button_iter = button_query_q0.iter_mut();
+        change_buttons(
+            &selected_branch.id,
+            &mut button_iter,
+            &mut text_query,
+            &mut visibility_query,
+            &build_menu,
+            &localization,
         );
     }
-    prev_q.pressed = keyboard_input.pressed(KeyCode::Q);
 }
 
 #[derive(Clone, Debug)]
 pub enum GuiOr {
-    Id(&'static str),
-    Item(&'static str),
+    Id(String),
+    Item(String),
     None,
 }
 
@@ -123,8 +147,14 @@ fn change_buttons(
     >,
     text_query: &mut Query<(&mut Text, &GuiTextId, Entity)>,
     visibility_query: &mut Query<&mut Visibility>,
+    build_menu: &BuildMenu,
+    localization: &Localization,
 ) {
-    let branch = GUI_LOOKUP.get(branch_id).unwrap();
+    // Nothing to show before the menu is loaded
+    let branch = match build_menu.branch(branch_id) {
+        Some(e) => &e.entries,
+        None => return,
+    };
 
     for (mut text, i, text_entity) in text_query.iter_mut() {
         let button_content = &branch[i.id as usize];
@@ -141,12 +171,13 @@ fn change_buttons(
             .unwrap()
             .is_visible = true;
         match &button_content {
-            // Set the text in the button                                  Ignore everything before underscores
-            GuiOr::Id(e) => {
-                text.sections[0].value = e.to_string().rsplitn(2, "_").next().unwrap().to_string()
-            }
-            GuiOr::Item(e) => {
-                text.sections[0].value = e.to_string().rsplitn(2, "_").next().unwrap().to_string()
+            // Set the text in the button
+            GuiOr::Id(_) | GuiOr::Item(_) => {
+                text.sections[0].value = label(button_content, localization);
+                text.sections[0].style.color = match build_menu.is_available(button_content) {
+                    true => TEXT_COLOR,
+                    false => UNAVAILABLE_TEXT_COLOR,
+                };
             }
 
             // Hide the button
@@ -177,17 +208,85 @@ fn click_button(
     >,
     visibility_query: &mut Query<&mut Visibility>,
     selected_building: &mut ResMut<SelectedBuilding>,
+    build_menu: &BuildMenu,
+    localization: &Localization,
 ) {
     match clicked_button_content {
         GuiOr::Id(e) => {
             selected_branch.id = e.to_string();
-            change_buttons(&e.to_string(), button_iter, text_query, visibility_query);
+            change_buttons(
+                e,
+                button_iter,
+                text_query,
+                visibility_query,
+                build_menu,
+                localization,
+            );
         }
         GuiOr::Item(e) => {
-            selected_building.id = Some(string_to_building_enum(e.to_string()));
+            if !build_menu.is_available(clicked_button_content) {
+                return;
+            }
+            selected_building.id = string_to_building_enum(e.to_string());
             selected_building.changed = true;
             info!("you selected {:?}!", e);
         }
         _ => (),
     }
 }
+
+/// Shows the "unimplemented" tooltip next to the cursor while it's over an unavailable item
+pub fn update_gui_tooltip(
+    windows: Res<Windows>,
+    build_menu: Res<BuildMenu>,
+    localization: Res<Localization>,
+    button_query: Query<(&Interaction, &GuiButtons)>,
+    mut tooltip_query: Query<(&mut Style, &mut Visibility, &mut Text), With<GuiTooltip>>,
+) {
+    let (mut style, mut visibility, mut text) = match tooltip_query.get_single_mut() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    if localization.is_changed() {
+        text.sections[0].value = localization.get("gui.unimplemented").to_string();
+    }
+
+    let hovering_unavailable = button_query.iter().any(|(interaction, button)| {
+        *interaction != Interaction::None
+            && matches!(button.content, GuiOr::Item(_))
+            && !build_menu.is_available(&button.content)
+    });
+    let cursor = windows.get_primary().and_then(|e| e.cursor_position());
+
+    match (hovering_unavailable, cursor) {
+        (true, Some(cursor)) => {
+            style.position.left = Val::Px(cursor.x + 16.0);
+            style.position.bottom = Val::Px(cursor.y - 8.0);
+            if !visibility.is_visible {
+                visibility.is_visible = true;
+            }
+        }
+        _ => {
+            if visibility.is_visible {
+                visibility.is_visible = false;
+            }
+        }
+    }
+}
+
+/// Marks items that have no building as unavailable, once the buildings are loaded
+pub fn resolve_menu_items(mut build_menu: ResMut<BuildMenu>, buildings: Res<BuildingsResource>) {
+    if build_menu.resolved() {
+        return;
+    }
+
+    let missing = build_menu.resolve(&buildings);
+    if !missing.is_empty() {
+        warn!(
+            "{} build menu items have no building yet: {}",
+            missing.len(),
+            missing.join(", ")
+        );
+    }
+}
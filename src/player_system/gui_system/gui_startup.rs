@@ -1,14 +1,16 @@
 use bevy::prelude::*;
 
-use crate::{
-    building_system::buildings::BuildingType,
-    constants::{ShortToString, GUI_LOOKUP},
-};
+use crate::building_system::buildings::BuildingType;
 
-use super::gui::GuiOr;
+use super::{
+    build_menu::{BuildMenu, BuildMenuHandle, BUILD_MENU_PATH, BUILD_MENU_ROOT},
+    gui::GuiOr,
+};
 
 pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
 pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
+pub const UNAVAILABLE_TEXT_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
 
 pub struct GuiSelectedBranch {
     pub id: String,
@@ -30,15 +32,15 @@ pub struct GuiTextId {
     pub id: u32,
 }
 
+/// Shown while hovering an item that can't be built yet
+#[derive(Component)]
+pub struct GuiTooltip;
+
 #[derive(Component)]
 pub struct GuiTextBox {
     pub id: u32,
 }
 
-pub struct PrevQPress {
-    pub pressed: bool,
-}
-
 pub struct SelectedBuilding {
     pub id: Option<BuildingType>,
     pub changed: bool,
@@ -49,13 +51,16 @@ pub fn gui_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
 
     // ui camera
     commands.spawn_bundle(UiCameraBundle::default());
-    commands.insert_resource(GuiSelectedBranch { id: "base".ts() });
-    commands.insert_resource(PrevQPress { pressed: false });
+    commands.insert_resource(GuiSelectedBranch {
+        id: BUILD_MENU_ROOT.to_string(),
+    });
     commands.insert_resource(SelectedBuilding {
         id: None,
         changed: false,
     });
-    let branch = GUI_LOOKUP.get(&"base".ts()).unwrap();
+    // The buttons stay empty until `apply_build_menu` has the menu
+    commands.insert_resource(BuildMenu::default());
+    commands.insert_resource(BuildMenuHandle(asset_server.load(BUILD_MENU_PATH)));
     let mut margin = Rect::default();
     margin.left = Val::Px(14.0);
     // base node bundle
@@ -91,7 +96,7 @@ pub fn gui_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         ..Default::default()
                     })
                     .insert(GuiButtons {
-                        content: branch[j].clone(),
+                        content: GuiOr::None,
                         id: j as u32,
                     })
                     .insert(GuiButtonId { id: 3 - j as u32 })
@@ -118,14 +123,11 @@ pub fn gui_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
                                 parent
                                     .spawn_bundle(TextBundle {
                                         text: Text::with_section(
-                                            match &branch[j] {
-                                                GuiOr::Id(e) => e.clone(),
-                                                _ => panic!("you suck"),
-                                            },
+                                            String::new(),
                                             TextStyle {
                                                 font: asset_server.load("fonts/zekton-rg.ttf"),
                                                 font_size: 26.0,
-                                                color: Color::rgb(0.9, 0.9, 0.9),
+                                                color: TEXT_COLOR,
                                             },
                                             Default::default(),
                                         ),
@@ -136,5 +138,26 @@ pub fn gui_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
                     });
             }
         });
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            text: Text::with_section(
+                // Set by `update_gui_tooltip`
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/zekton-rg.ttf"),
+                    font_size: 20.0,
+                    color: TEXT_COLOR,
+                },
+                Default::default(),
+            ),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(GuiTooltip);
+
     info!("gui done");
 }
@@ -1,2 +1,3 @@
+pub mod build_menu;
 pub mod gui_startup;
 pub mod gui;
\ No newline at end of file
@@ -0,0 +1,373 @@
+use std::{collections::HashMap as DefinitionMap, marker::PhantomData};
+
+use bevy::{
+    ecs::system::SystemParam,
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    utils::HashMap,
+};
+use serde::Deserialize;
+
+/// Where the bindings are read from at startup, actions it leaves out keep their default bindings
+pub const INPUT_BINDINGS_PATH: &str = "config/input_bindings.ron";
+
+/// The bindings file built into the game, the only place the default bindings are written down
+const DEFAULT_BINDINGS: &str = include_str!("../../config/input_bindings.ron");
+
+/// How far a gamepad axis has to be pushed to count as pressed
+pub const AXIS_DEADZONE: f32 = 0.05;
+
+/// Everything the player can do with a button, systems ask for these instead of specific keys
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Descend,
+    /// Uses the closest `Interactable`, also gets out of vehicles and seats
+    Interact,
+    /// Uses the building under the cursor (gates, garages, spatial anchors...)
+    UseBuilding,
+    /// Turns the building being placed by a quarter
+    Rotate,
+    /// Held to turn the building being placed with the scroll wheel instead of zooming
+    RotateModifier,
+    Place,
+    /// Held over a blueprint to fill it
+    Fabricate,
+    MenuSlot1,
+    MenuSlot2,
+    MenuSlot3,
+    MenuSlot4,
+    MenuBack,
+    /// Held to turn the camera with the mouse
+    CameraOrbit,
+    ZoomIn,
+    ZoomOut,
+    /// Held to make `ZoomIn` and `ZoomOut` faster
+    ZoomFast,
+    Recipe1,
+    Recipe2,
+    Recipe3,
+    Recipe4,
+    Recipe5,
+    Recipe6,
+    Recipe7,
+    Recipe8,
+    Recipe9,
+    /// Writes the world to `GameSettings::save_path`
+    SaveWorld,
+    SpawnDebugCube,
+    /// Damages the building under the cursor
+    DamageBuilding,
+}
+
+impl Action {
+    pub const MENU_SLOTS: [Action; 4] = [
+        Action::MenuSlot1,
+        Action::MenuSlot2,
+        Action::MenuSlot3,
+        Action::MenuSlot4,
+    ];
+
+    pub const RECIPES: [Action; 9] = [
+        Action::Recipe1,
+        Action::Recipe2,
+        Action::Recipe3,
+        Action::Recipe4,
+        Action::Recipe5,
+        Action::Recipe6,
+        Action::Recipe7,
+        Action::Recipe8,
+        Action::Recipe9,
+    ];
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxisDirection {
+    Positive,
+    Negative,
+}
+
+/// Something that can trigger an action, any number of them can point at the same action
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    GamepadButton(GamepadButtonType),
+    /// Pressed while the axis is pushed past `AXIS_DEADZONE`, never counts as just pressed
+    GamepadAxis(GamepadAxisType, AxisDirection),
+}
+
+/// Everything the player controls with an analog input, systems ask for these instead of specific
+/// sticks or the scroll wheel
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AxisAction {
+    CameraYaw,
+    CameraPitch,
+    /// Positive zooms in, used while `Action::RotateModifier` isn't held
+    Zoom,
+    /// Turns the building being placed while `Action::RotateModifier` is held
+    RotateBuilding,
+}
+
+/// Something that can drive an axis action, the values of every binding are added up
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AxisBinding {
+    /// Ignored while it's within `AXIS_DEADZONE`
+    GamepadAxis(GamepadAxisType),
+    /// Lines (or notches) scrolled this frame
+    ScrollWheel,
+}
+
+/// The bindings file, the same shape as `InputBindings`
+#[derive(Deserialize, Debug)]
+struct InputBindingsDefinition {
+    bindings: DefinitionMap<Action, Vec<Binding>>,
+    #[serde(default)]
+    axes: DefinitionMap<AxisAction, Vec<AxisBinding>>,
+}
+
+pub struct InputBindings {
+    bindings: HashMap<Action, Vec<Binding>>,
+    axes: HashMap<AxisAction, Vec<AxisBinding>>,
+}
+
+impl Default for InputBindings {
+    /// The bindings the game ships with, `INPUT_BINDINGS_PATH` as it was when the game was built
+    fn default() -> Self {
+        InputBindings::parse(DEFAULT_BINDINGS)
+            .unwrap_or_else(|e| panic!("{:?} as shipped: {}", INPUT_BINDINGS_PATH, e))
+    }
+}
+
+impl InputBindings {
+    /// Reads the bindings file on top of the defaults, logs and keeps the defaults if it's missing or
+    /// invalid
+    pub fn load(path: &str) -> Self {
+        let mut input_bindings = InputBindings::default();
+
+        let bytes = match std::fs::read(path) {
+            Ok(e) => e,
+            Err(e) => {
+                info!("{:?}: {}, using the default bindings", path, e);
+                return input_bindings;
+            }
+        };
+
+        match ron::de::from_bytes::<InputBindingsDefinition>(&bytes) {
+            Ok(definition) => {
+                input_bindings.bindings.extend(definition.bindings);
+                input_bindings.axes.extend(definition.axes);
+            }
+            Err(e) => error!("{:?}: {}, using the default bindings", path, e),
+        }
+
+        input_bindings
+    }
+
+    /// Only the actions in `text`, for the defaults
+    fn parse(text: &str) -> Result<Self, ron::Error> {
+        let definition = ron::de::from_str::<InputBindingsDefinition>(text)?;
+
+        Ok(InputBindings {
+            bindings: definition.bindings.into_iter().collect(),
+            axes: definition.axes.into_iter().collect(),
+        })
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |e| e.as_slice())
+    }
+
+    pub fn axis_bindings(&self, axis: AxisAction) -> &[AxisBinding] {
+        self.axes.get(&axis).map_or(&[], |e| e.as_slice())
+    }
+
+    /// Name of the first thing bound to `action`, for prompts
+    pub fn label(&self, action: Action) -> String {
+        match self.bindings(action).first() {
+            // Number keys are called Key1, Key2...
+            Some(Binding::Key(key)) => {
+                let name = format!("{:?}", key);
+                name.strip_prefix("Key").unwrap_or(&name).to_string()
+            }
+            Some(Binding::Mouse(button)) => format!("Mouse {:?}", button),
+            Some(Binding::GamepadButton(button)) => format!("{:?}", button),
+            Some(Binding::GamepadAxis(axis, direction)) => format!("{:?} {:?}", axis, direction),
+            None => "(unbound)".to_string(),
+        }
+    }
+}
+
+/// Everything needed to check actions, use it instead of reading the keyboard, mouse or gamepads
+#[derive(SystemParam)]
+pub struct ActionInput<'w, 's> {
+    input_bindings: Res<'w, InputBindings>,
+    keyboard_input: Res<'w, Input<KeyCode>>,
+    mouse_input: Res<'w, Input<MouseButton>>,
+    gamepads: Res<'w, Gamepads>,
+    gamepad_input: Res<'w, Input<GamepadButton>>,
+    gamepad_axes: Res<'w, Axis<GamepadAxis>>,
+    scroll: Res<'w, ScrollInput>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl<'w, 's> ActionInput<'w, 's> {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.input_bindings
+            .bindings(action)
+            .iter()
+            .any(|e| self.binding_pressed(*e))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.input_bindings
+            .bindings(action)
+            .iter()
+            .any(|e| self.binding_just_pressed(*e))
+    }
+
+    fn binding_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard_input.pressed(key),
+            Binding::Mouse(button) => self.mouse_input.pressed(button),
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .iter()
+                .any(|e| self.gamepad_input.pressed(GamepadButton(*e, button))),
+            Binding::GamepadAxis(axis, direction) => self.gamepads.iter().any(|e| {
+                let value = self.gamepad_axes.get(GamepadAxis(*e, axis)).unwrap_or(0.0);
+                match direction {
+                    AxisDirection::Positive => value > AXIS_DEADZONE,
+                    AxisDirection::Negative => value < -AXIS_DEADZONE,
+                }
+            }),
+        }
+    }
+
+    fn binding_just_pressed(&self, binding: Binding) -> bool {
+        match binding {
+            Binding::Key(key) => self.keyboard_input.just_pressed(key),
+            Binding::Mouse(button) => self.mouse_input.just_pressed(button),
+            Binding::GamepadButton(button) => self
+                .gamepads
+                .iter()
+                .any(|e| self.gamepad_input.just_pressed(GamepadButton(*e, button))),
+            Binding::GamepadAxis(..) => false,
+        }
+    }
+
+    /// Value of an axis action, the sum of everything bound to it
+    pub fn axis(&self, axis: AxisAction) -> f32 {
+        self.input_bindings
+            .axis_bindings(axis)
+            .iter()
+            .map(|e| match e {
+                AxisBinding::GamepadAxis(axis) => {
+                    let value = self.gamepad_axis(*axis);
+                    match value.abs() > AXIS_DEADZONE {
+                        true => value,
+                        false => 0.0,
+                    }
+                }
+                AxisBinding::ScrollWheel => self.scroll.0,
+            })
+            .sum()
+    }
+
+    /// Analog value of a gamepad axis, the one pushed furthest if there are several gamepads
+    fn gamepad_axis(&self, axis: GamepadAxisType) -> f32 {
+        self.gamepads
+            .iter()
+            .filter_map(|e| self.gamepad_axes.get(GamepadAxis(*e, axis)))
+            .fold(0.0, |a, b| if b.abs() > a.abs() { b } else { a })
+    }
+}
+
+/// How far the scroll wheel was turned this frame, in lines
+#[derive(Default)]
+pub struct ScrollInput(pub f32);
+
+/// Adds up this frame's scroll events, pixel scrolling (touchpads) counts a line every 16 pixels
+pub fn read_scroll(mut scroll_events: EventReader<MouseWheel>, mut scroll: ResMut<ScrollInput>) {
+    scroll.0 = scroll_events
+        .iter()
+        .map(|e| match e.unit {
+            MouseScrollUnit::Line => e.y,
+            MouseScrollUnit::Pixel => e.y / 16.0,
+        })
+        .sum();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_bindings_parse() {
+        let bindings = InputBindings::default();
+
+        assert_eq!(
+            bindings.bindings(Action::Jump),
+            &[Binding::Key(KeyCode::Space)]
+        );
+        assert_eq!(
+            bindings.axis_bindings(AxisAction::Zoom),
+            &[AxisBinding::ScrollWheel]
+        );
+    }
+
+    #[test]
+    fn no_key_does_two_things() {
+        let bindings = InputBindings::default();
+
+        // Actions that are meant to share a button
+        let shared = [Action::Place, Action::Fabricate];
+
+        let mut seen: Vec<(Binding, Action)> = Vec::new();
+        for (action, action_bindings) in bindings.bindings.iter() {
+            for binding in action_bindings {
+                if let Some((_, other)) = seen.iter().find(|(e, _)| e == binding) {
+                    assert!(
+                        shared.contains(action) && shared.contains(other),
+                        "{:?} is bound to both {:?} and {:?}",
+                        binding,
+                        action,
+                        other
+                    );
+                }
+                seen.push((*binding, *action));
+            }
+        }
+    }
+
+    #[test]
+    fn the_file_overrides_only_what_it_has() {
+        let mut bindings = InputBindings::default();
+        let file = InputBindings::parse("(bindings: { Jump: [Key(J)] })").unwrap();
+        bindings.bindings.extend(file.bindings);
+
+        assert_eq!(bindings.bindings(Action::Jump), &[Binding::Key(KeyCode::J)]);
+        assert_eq!(
+            bindings.bindings(Action::Rotate),
+            &[Binding::Key(KeyCode::R)]
+        );
+    }
+
+    #[test]
+    fn labels() {
+        let bindings = InputBindings::parse(
+            "(bindings: { Recipe1: [Key(Key1)], Place: [Mouse(Left)], MenuBack: [] })",
+        )
+        .unwrap();
+
+        assert_eq!(bindings.label(Action::Recipe1), "1");
+        assert_eq!(bindings.label(Action::Place), "Mouse Left");
+        assert_eq!(bindings.label(Action::MenuBack), "(unbound)");
+        assert_eq!(bindings.label(Action::Jump), "(unbound)");
+    }
+}
@@ -0,0 +1,159 @@
+use std::fmt::Display;
+
+use bevy::prelude::*;
+
+use crate::{localization::Localization, settings::GameSettings};
+
+use super::{
+    input::{Action, ActionInput, InputBindings},
+    player::Player,
+    seat::Seated,
+    vehicle::Driving,
+};
+
+/// Something the player can use by walking up to it and pressing E
+#[derive(Component)]
+pub struct Interactable {
+    /// String table key of what's shown after "Press E to", e.g. `prompt.sit`
+    ///
+    /// It's looked up every frame, so prompts follow the string tables as they load
+    pub prompt: String,
+    /// Values of the `{name}`s in the prompt
+    pub args: Vec<(&'static str, String)>,
+}
+
+impl Interactable {
+    pub fn new(prompt: &str) -> Self {
+        Interactable {
+            prompt: prompt.to_string(),
+            args: Vec::new(),
+        }
+    }
+}
+
+/// The closest `Interactable` in reach of the player, if there is one
+#[derive(Default)]
+pub struct InteractionTarget(pub Option<Entity>);
+
+/// Sent when the player uses an `Interactable`
+///
+/// Whatever owns the interactable (vehicles, seats...) reads these and checks if `entity` is theirs
+pub struct InteractEvent {
+    pub entity: Entity,
+}
+
+#[derive(Component)]
+pub struct InteractionPrompt;
+
+pub fn interaction_prompt_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    bottom: Val::Px(120.0),
+                    left: Val::Percent(45.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            text: Text::with_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/zekton-rg.ttf"),
+                    font_size: 26.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                Default::default(),
+            ),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(InteractionPrompt);
+}
+
+/// Picks the closest interactable in reach, nothing can be used while driving or sitting
+pub fn find_interaction_target(
+    mut target: ResMut<InteractionTarget>,
+    settings: Res<GameSettings>,
+
+    player_query: Query<&GlobalTransform, (With<Player>, Without<Driving>, Without<Seated>)>,
+    interactable_query: Query<(Entity, &GlobalTransform), With<Interactable>>,
+) {
+    let player_position = match player_query.get_single() {
+        Ok(e) => e.translation,
+        Err(_) => {
+            if target.0.is_some() {
+                target.0 = None;
+            }
+            return;
+        }
+    };
+
+    let closest = interactable_query
+        .iter()
+        .map(|(entity, transform)| (entity, transform.translation.distance(player_position)))
+        .filter(|(_, distance)| *distance <= settings.interact_distance)
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(entity, _)| entity);
+
+    // Only touch the resource when it changes so the prompt isn't rebuilt every frame
+    if target.0 != closest {
+        target.0 = closest;
+    }
+}
+
+pub fn interaction_input(
+    input: ActionInput,
+    target: Res<InteractionTarget>,
+    mut interact_events: EventWriter<InteractEvent>,
+) {
+    if let Some(entity) = target.0 {
+        if input.just_pressed(Action::Interact) {
+            interact_events.send(InteractEvent { entity });
+        }
+    }
+}
+
+/// Shows "Press E to ..." for the current target, with whatever `Action::Interact` is bound to
+pub fn update_interaction_prompt(
+    target: Res<InteractionTarget>,
+    input_bindings: Res<InputBindings>,
+    localization: Res<Localization>,
+    interactable_query: Query<&Interactable>,
+    mut prompt_query: Query<(&mut Text, &mut Visibility), With<InteractionPrompt>>,
+) {
+    let (mut text, mut visibility) = match prompt_query.get_single_mut() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    // Prompts can change while they're being looked at (transceivers), so this checks every frame
+    match target.0.and_then(|e| interactable_query.get(e).ok()) {
+        Some(interactable) => {
+            let args: Vec<(&str, &dyn Display)> = interactable
+                .args
+                .iter()
+                .map(|(name, value)| (*name, value as &dyn Display))
+                .collect();
+            let value = localization.format(
+                "prompt.interact",
+                &[
+                    ("key", &input_bindings.label(Action::Interact)),
+                    ("action", &localization.format(&interactable.prompt, &args)),
+                ],
+            );
+            if text.sections[0].value != value {
+                text.sections[0].value = value;
+            }
+            if !visibility.is_visible {
+                visibility.is_visible = true;
+            }
+        }
+        None => {
+            if visibility.is_visible {
+                visibility.is_visible = false;
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use bevy::{prelude::*, utils::HashMap};
+
+/// Items held by the player or a building, by item id
+#[derive(Component, Default, Debug)]
+pub struct Inventory(pub HashMap<String, u32>);
+
+impl Inventory {
+    pub fn add(&mut self, item: &str, amount: u32) {
+        *self.0.entry(item.to_string()).or_insert(0) += amount;
+    }
+
+    pub fn count(&self, item: &str) -> u32 {
+        self.0.get(item).copied().unwrap_or(0)
+    }
+
+    /// Moves everything in `other` into `self`
+    pub fn take_all(&mut self, other: &mut Inventory) {
+        for (item, amount) in other.0.drain() {
+            *self.0.entry(item).or_insert(0) += amount;
+        }
+    }
+}
@@ -1,28 +1,74 @@
 use bevy::{
     core::FixedTimestep,
-    prelude::{Plugin, SystemSet},
+    prelude::{AddAsset, CoreStage, ParallelSystemDescriptorCoercion, Plugin, SystemSet},
 };
 
-use self::{player::{player_movement_system, player_collider_debug}, player_startup::PlayerStartupDone, gui_system::gui::gui};
-use crate::player_system::gui_system::gui_startup::*;
+use self::{player::{player_movement_system, player_collider_debug}, player_startup::PlayerStartupDone, gui_system::{gui::{gui, resolve_menu_items, update_gui_tooltip}, build_menu::{BuildMenu, BuildMenuLoader, apply_build_menu, warn_missing_menu_labels}}, vehicle::{carry_driver, drive_vehicles, enter_vehicle, exit_vehicle}, interaction::{InteractEvent, InteractionTarget, find_interaction_target, interaction_input, interaction_prompt_startup, update_interaction_prompt}, seat::{carry_seated, follow_mounts, init_seats, leave_seats, use_seats}, respawn::{PlayerDeathEvent, check_kill_plane, mark_spawn_points, respawn_player}};
+use self::input::{read_scroll, InputBindings, ScrollInput, INPUT_BINDINGS_PATH};
+use crate::{building_system::buildings::building_init_done, player_system::gui_system::gui_startup::*};
 
 pub mod player;
 pub mod player_startup;
 pub mod gui_system;
+pub mod inventory;
+pub mod vehicle;
+pub mod interaction;
+pub mod input;
+pub mod seat;
+pub mod respawn;
 
 pub struct PlayerSystemPlugin;
 
 impl Plugin for PlayerSystemPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
-        app.insert_resource(PlayerStartupDone { done: false });
-        app.add_system(player_startup::player_start)
+        app.insert_resource(PlayerStartupDone { done: false })
+            .insert_resource(InputBindings::load(INPUT_BINDINGS_PATH))
+            .init_resource::<ScrollInput>()
+            .init_resource::<InteractionTarget>()
+            .add_event::<InteractEvent>()
+            .add_event::<PlayerDeathEvent>();
+        app.add_system_to_stage(CoreStage::PreUpdate, read_scroll)
+            .add_system(player_startup::player_start)
             .add_system_set(
                 SystemSet::new()
                     .with_run_criteria(FixedTimestep::steps_per_second(60.0))
-                    .with_system(player_movement_system),
+                    .with_system(player_movement_system)
+                    .with_system(drive_vehicles),
+            )
+            .add_startup_system(interaction_prompt_startup)
+            .add_system(find_interaction_target)
+            .add_system(interaction_input.after(find_interaction_target))
+            .add_system(update_interaction_prompt.after(find_interaction_target))
+            .add_system(enter_vehicle.after(interaction_input))
+            .add_system(exit_vehicle)
+            // Before getting out, it'd put the player back in the seat in the frame they got out
+            .add_system(carry_driver.before(exit_vehicle))
+            .add_system(init_seats)
+            .add_system(use_seats.after(interaction_input))
+            .add_system(leave_seats)
+            .add_system(follow_mounts)
+            .add_system(carry_seated.after(follow_mounts))
+            .add_system(mark_spawn_points)
+            .add_system(check_kill_plane)
+            // After the carry systems, they'd put a player who died driving or seated back in the same frame
+            .add_system(
+                respawn_player
+                    .after(check_kill_plane)
+                    .after(carry_driver)
+                    .after(carry_seated),
             )
             .add_system(player_collider_debug)
+            .add_asset::<BuildMenu>()
+            .init_asset_loader::<BuildMenuLoader>()
             .add_startup_system(gui_startup)
-            .add_system(gui);
+            .add_system(apply_build_menu.before(gui))
+            .add_system(warn_missing_menu_labels.after(apply_build_menu))
+            .add_system(gui)
+            .add_system(update_gui_tooltip)
+            .add_system_set(
+                SystemSet::new()
+                    .with_run_criteria(building_init_done)
+                    .with_system(resolve_menu_items),
+            );
     }
 }
@@ -1,8 +1,10 @@
-use bevy::{prelude::*, input::mouse::{MouseMotion, MouseWheel}};
+use bevy::{prelude::*, input::mouse::MouseMotion};
 use bevy_mod_raycast::RayCastMesh;
 use bevy_rapier3d::prelude::*;
 
-use crate::{constants::HALF_PI, building_system::RaycastSet};
+use crate::{constants::HALF_PI, building_system::RaycastSet, settings::GameSettings};
+
+use super::{input::{Action, ActionInput, AxisAction}, seat::Seated, vehicle::Driving};
 
 #[derive(Component)]
 pub struct Player {
@@ -10,6 +12,14 @@ pub struct Player {
     pub speed: f32,
 }
 
+/// How `player_movement_system` turns input into velocity
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MovementMode {
+    Walking,
+    /// On a ladder, forward and back move the player up and down
+    Climbing,
+}
+
 #[derive(Component, Debug)]
 pub struct CameraComp {
     pub yaw: f32,
@@ -19,24 +29,52 @@ pub struct CameraComp {
 
 
 pub fn player_movement_system(
-    keyboard_input: Res<Input<KeyCode>>,
-
-    gamepads: Res<Gamepads>,
-    gamepad_input: Res<Input<GamepadButton>>,
-    gamepad_axes: Res<Axis<GamepadAxis>>,
+    input: ActionInput,
+    settings: Res<GameSettings>,
 
     c_query: Query<&mut CameraComp>,
-    mut r_query: Query<&mut Velocity, (Without<CameraComp>, With<Player>)>,
+    mut r_query: Query<(&mut Velocity, &MovementMode), (Without<CameraComp>, With<Player>, Without<Driving>, Without<Seated>)>,
     p_query: Query<&Player, Without<CameraComp>>,
 ) {
     let r_option = r_query.get_single_mut();
-    let mut player_rigidbody = match r_option {
+    let (mut player_rigidbody, mode) = match r_option {
         Ok(e) => e,
         Err(_) => return,
     };
     let player = p_query.single();
     let camera = c_query.single();
 
+    let mut mov = movement_input(&input, camera);
+
+    match mode {
+        MovementMode::Walking => {
+            if input.pressed(Action::Jump) {
+                player_rigidbody.linvel.y = 10.0;
+            }
+
+            if input.pressed(Action::Descend) {
+                player_rigidbody.linvel.y = -10.0;
+            }
+        }
+        MovementMode::Climbing => {
+            // Split off the forward/back part of the input and use it to climb, strafing still works
+            let yaw = camera.yaw.to_radians();
+            let forward = -Vec2::new(yaw.cos(), yaw.sin());
+            let climb = mov.dot(forward);
+
+            mov -= forward * climb;
+            player_rigidbody.linvel.y = climb * settings.climb_speed;
+        }
+    }
+
+    player_rigidbody.linvel.x = mov.x * player.speed;
+    player_rigidbody.linvel.z = mov.y * player.speed;
+}
+
+/// Turns the movement actions into a normalized direction relative to the camera
+///
+/// Used by both the player and whatever vehicle they're driving
+pub fn movement_input(input: &ActionInput, camera: &CameraComp) -> Vec2 {
     // .y is actually in the Z direction
     let mut mov = Vec2::ZERO;
 
@@ -48,46 +86,19 @@ pub fn player_movement_system(
     let cos_yaw_half = (yaw + HALF_PI).cos();
     let sin_yaw_half = (yaw + HALF_PI).sin();
 
-    // Get gamepad inputs
-    for gamepad in gamepads.iter().cloned() {
-        let button_pressed = |button| {
-            gamepad_input.pressed(GamepadButton(gamepad, button))
-        };
-        let axes_moved = |axis| {
-            gamepad_axes.get(GamepadAxis(gamepad, axis)).unwrap()
-        };
-
-        if button_pressed(GamepadButtonType::DPadUp) || axes_moved(GamepadAxisType::LeftStickY) > 0.05 {
-            mov.x -= cos_yaw;
-            mov.y -= sin_yaw;
-        }
-        if button_pressed(GamepadButtonType::DPadDown) || axes_moved(GamepadAxisType::LeftStickY) < -0.05 {
-            mov.x += cos_yaw;
-            mov.y += sin_yaw;
-        }
-        if button_pressed(GamepadButtonType::DPadLeft) || axes_moved(GamepadAxisType::LeftStickX) > 0.05 {
-            mov.x += cos_yaw_half;
-            mov.y += sin_yaw_half;
-        }
-        if button_pressed(GamepadButtonType::DPadRight) || axes_moved(GamepadAxisType::LeftStickX) < -0.05 {
-            mov.x -= cos_yaw_half;
-            mov.y -= sin_yaw_half;
-        }
-    }
-    // Get keyboard inputs
-    if keyboard_input.pressed(KeyCode::W) {
+    if input.pressed(Action::MoveForward) {
         mov.x -= cos_yaw;
         mov.y -= sin_yaw;
     }
-    if keyboard_input.pressed(KeyCode::S) {
+    if input.pressed(Action::MoveBack) {
         mov.x += cos_yaw;
         mov.y += sin_yaw;
     }
-    if keyboard_input.pressed(KeyCode::A) {
+    if input.pressed(Action::MoveLeft) {
         mov.x += cos_yaw_half;
         mov.y += sin_yaw_half;
     }
-    if keyboard_input.pressed(KeyCode::D) {
+    if input.pressed(Action::MoveRight) {
         mov.x -= cos_yaw_half;
         mov.y -= sin_yaw_half;
     }
@@ -97,29 +108,14 @@ pub fn player_movement_system(
         mov = mov.normalize();
     }
 
-    if keyboard_input.pressed(KeyCode::Space) {
-        player_rigidbody.linvel.y = 10.0;
-    }
-
-    if keyboard_input.pressed(KeyCode::B) {
-        player_rigidbody.linvel.y = -10.0;
-    }
-
-    player_rigidbody.linvel.x = mov.x * player.speed;
-    player_rigidbody.linvel.z = mov.y * player.speed;
+    mov
 }
 
 pub fn player_camera_system(
     mut mouse_motion_event: EventReader<MouseMotion>,
-    mouse_input: Res<Input<MouseButton>>,
-    mut mouse_scroll_event: EventReader<MouseWheel>,
-
-    gamepads: Res<Gamepads>,
-    gamepad_input: Res<Input<GamepadButton>>,
-    gamepad_axes: Res<Axis<GamepadAxis>>,
+    input: ActionInput,
 
     mut c_query: Query<(&mut CameraComp, &mut Transform)>,
-    keyboard_input: Res<Input<KeyCode>>,
 ) {
     let c_option = c_query.get_single_mut();
 
@@ -132,63 +128,44 @@ pub fn player_camera_system(
 
     let last_camera_zoom = camera.zoom;
 
-    if !keyboard_input.pressed(KeyCode::LShift) {
-        for event in mouse_scroll_event.iter() {
-            camera.zoom -= (event.y / 3.0) * camera.zoom.sqrt();
+    // The scroll wheel turns the building being placed instead
+    if !input.pressed(Action::RotateModifier) {
+        let zoom = input.axis(AxisAction::Zoom);
+        if zoom != 0.0 {
+            camera.zoom -= (zoom / 3.0) * camera.zoom.sqrt();
             camera.zoom = camera.zoom.max(0.1).min(100.0);
         }
     }
     
 
-    let rmb_pressed = mouse_input.pressed(MouseButton::Right);
+    let orbit_pressed = input.pressed(Action::CameraOrbit);
 
     let mut gamepad_axes_moved = false;
 
-    for gamepad in gamepads.iter().cloned() {
-        let button_pressed = |button| {
-            gamepad_input.pressed(GamepadButton(gamepad, button))
-        };
-        let axes_moved = |axis| {
-            gamepad_axes.get(GamepadAxis(gamepad, axis)).unwrap()
-        };
-
-        let rsy = axes_moved(GamepadAxisType::RightStickY);
-        let rsx = axes_moved(GamepadAxisType::RightStickX);
-        if rsy > 0.05 {
-            camera.roll += 2.0 * rsy;
-            gamepad_axes_moved = true;
-        }
-        if rsy < -0.05 {
-            camera.roll += 2.0 * rsy;
-            gamepad_axes_moved = true;
-        }
-        if rsx > 0.05 {
-            camera.yaw += 2.0 * rsx;
-            gamepad_axes_moved = true;
-        }
-        if rsx < -0.05 {
-            camera.yaw += 2.0 * rsx;
-            gamepad_axes_moved = true;
-        }
-        
-        if button_pressed(GamepadButtonType::RightThumb) {
-            if button_pressed(GamepadButtonType::RightTrigger) {
-                camera.zoom += 1.0;
-            } else {
-                camera.zoom += 0.1;
-            }
-        }
-        if button_pressed(GamepadButtonType::LeftThumb) {
-            if button_pressed(GamepadButtonType::LeftTrigger) {
-                camera.zoom -= 1.0;
-            } else {
-                camera.zoom -= 0.1;
-            }
-        }
+    let pitch = input.axis(AxisAction::CameraPitch);
+    let yaw = input.axis(AxisAction::CameraYaw);
+    if pitch != 0.0 {
+        camera.roll += 2.0 * pitch;
+        gamepad_axes_moved = true;
+    }
+    if yaw != 0.0 {
+        camera.yaw += 2.0 * yaw;
+        gamepad_axes_moved = true;
+    }
+
+    let zoom_step = match input.pressed(Action::ZoomFast) {
+        true => 1.0,
+        false => 0.1,
+    };
+    if input.pressed(Action::ZoomOut) {
+        camera.zoom += zoom_step;
+    }
+    if input.pressed(Action::ZoomIn) {
+        camera.zoom -= zoom_step;
     }
 
-    if rmb_pressed || last_camera_zoom != camera.zoom || gamepad_axes_moved {
-        if rmb_pressed {
+    if orbit_pressed || last_camera_zoom != camera.zoom || gamepad_axes_moved {
+        if orbit_pressed {
             for event in mouse_motion_event.iter() {
                 camera.yaw  += event.delta.x / 5.0;
                 camera.roll += event.delta.y / 5.0;
@@ -224,9 +201,9 @@ pub fn player_collider_debug(
     mut materials: ResMut<Assets<StandardMaterial>>,
 
     transform_query: Query<&Transform, With<Player>>,
-    keyboard_input: Res<Input<KeyCode>>,
+    input: ActionInput,
 ) {
-    if keyboard_input.just_pressed(KeyCode::B) {
+    if input.just_pressed(Action::SpawnDebugCube) {
         let mut transform = transform_query.single().clone();
         transform.translation.y += 10.0;
 
@@ -1,16 +1,24 @@
 use bevy::{
     pbr::{PbrBundle, StandardMaterial},
     prelude::{
-        shape, Assets, BuildChildren, Color, Commands, Mesh, PerspectiveCameraBundle, ResMut,
+        shape, Assets, BuildChildren, Color, Commands, Mesh, PerspectiveCameraBundle, Res, ResMut,
         Transform,
     },
 };
 use bevy_mod_picking::RayCastSource;
 use bevy_rapier3d::prelude::*;
 
-use crate::building_system::RaycastSet;
+use crate::{
+    building_system::RaycastSet,
+    constants::PLAYER_COLLISION,
+    settings::GameSettings,
+    terrain_generation_system::generator::{default_spawn_point, GeneratorOptions, TerrainSeed},
+};
 
-use super::player::{CameraComp, Player};
+use super::{
+    inventory::Inventory,
+    player::{CameraComp, MovementMode, Player},
+};
 
 pub struct PlayerStartupDone {
     pub done: bool,
@@ -21,6 +29,9 @@ pub fn player_start(
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     mut done: ResMut<PlayerStartupDone>,
+    generator_options: Res<GeneratorOptions>,
+    seed: Res<TerrainSeed>,
+    settings: Res<GameSettings>,
 ) {
     if done.done {
         return;
@@ -31,13 +42,15 @@ pub fn player_start(
         .spawn_bundle(PbrBundle {
             mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
             material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
-            transform: Transform::from_xyz(100.0, 175.0, 100.0),
+            transform: Transform::from_translation(default_spawn_point(&generator_options, &seed)),
             ..Default::default()
         })
         .insert(Player {
             name: "None".to_string(),
-            speed: 5.0,
+            speed: settings.player_speed,
         })
+        .insert(MovementMode::Walking)
+        .insert(Inventory::default())
         .insert(Collider::round_cuboid(0.4, 0.4, 0.4, 0.1))
         .insert(Friction {
             coefficient: 0.0,
@@ -46,10 +59,7 @@ pub fn player_start(
         .insert(Velocity::default())
         .insert(RigidBody::Dynamic)
         .insert(LockedAxes::ROTATION_LOCKED)
-        .insert(CollisionGroups {
-            memberships: 0b0010,
-            filters: 0b1111,
-        })
+        .insert(*PLAYER_COLLISION)
         .with_children(|child| {
             // camera
             child
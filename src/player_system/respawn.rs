@@ -0,0 +1,103 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{blueprint::BuildingCompletedEvent, buildings::BuildingType},
+    constants::{PLAYER_COLLISION, RESPAWN_OFFSET},
+    settings::GameSettings,
+    terrain_generation_system::generator::{default_spawn_point, GeneratorOptions, TerrainSeed},
+};
+
+use super::{
+    player::Player,
+    seat::{Seat, Seated},
+    vehicle::Driving,
+};
+
+/// A finished spawn point
+///
+/// There's only one team for now, so every spawn point belongs to the player
+#[derive(Component)]
+pub struct SpawnPoint;
+
+/// Send this when the player dies, they get respawned the same frame
+pub struct PlayerDeathEvent;
+
+pub fn mark_spawn_points(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type == BuildingType::SpawnPoint {
+            commands.entity(event.entity).insert(SpawnPoint);
+        }
+    }
+}
+
+/// Falling off the terrain counts as dying
+pub fn check_kill_plane(
+    settings: Res<GameSettings>,
+    player_query: Query<&Transform, With<Player>>,
+    mut death_events: EventWriter<PlayerDeathEvent>,
+) {
+    if let Ok(transform) = player_query.get_single() {
+        if transform.translation.y < settings.kill_plane_height {
+            death_events.send(PlayerDeathEvent);
+        }
+    }
+}
+
+/// Moves a dead player to the spawn point closest to where they died, or the terrain's default spawn
+/// if they don't have any
+///
+/// Players who die driving or seated are taken out of the vehicle or seat first
+pub fn respawn_player(
+    mut commands: Commands,
+    mut death_events: EventReader<PlayerDeathEvent>,
+    generator_options: Res<GeneratorOptions>,
+    seed: Res<TerrainSeed>,
+
+    mut player_query: Query<
+        (Entity, &mut Transform, &mut Velocity, Option<&Seated>),
+        (With<Player>, Without<SpawnPoint>),
+    >,
+    spawn_point_query: Query<&Transform, With<SpawnPoint>>,
+    mut seat_query: Query<&mut Seat>,
+) {
+    if death_events.iter().count() == 0 {
+        return;
+    }
+
+    let (entity, mut transform, mut velocity, seated) = match player_query.get_single_mut() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    let died_at = transform.translation;
+    let spawn = spawn_point_query
+        .iter()
+        .map(|e| e.translation)
+        .min_by(|a, b| {
+            a.distance_squared(died_at)
+                .partial_cmp(&b.distance_squared(died_at))
+                .unwrap()
+        });
+
+    transform.translation = match spawn {
+        Some(e) => e + *RESPAWN_OFFSET,
+        None => default_spawn_point(&generator_options, &seed),
+    };
+    *velocity = Velocity::default();
+
+    // Dead players don't stay in their vehicle or on their seat
+    if let Some(seated) = seated {
+        if let Ok(mut seat) = seat_query.get_mut(seated.0) {
+            seat.occupant = None;
+        }
+    }
+    commands
+        .entity(entity)
+        .remove_bundle::<(Driving, Seated)>()
+        .insert(RigidBody::Dynamic)
+        .insert(*PLAYER_COLLISION);
+}
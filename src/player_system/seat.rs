@@ -0,0 +1,227 @@
+use bevy::{math::const_vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent,
+        building_functions::MoveTransform,
+        buildings::{BuildingReferenceComponent, BuildingType},
+    },
+    collision_layers::CollisionLayers,
+    constants::{NO_COLLISION, PLAYER_COLLISION, SEAT_EXIT_OFFSET},
+};
+
+use super::{
+    input::{Action, ActionInput},
+    interaction::{InteractEvent, Interactable},
+    player::Player,
+};
+
+/// Where the player sits, relative to the seat
+const SIT_OFFSET: Vec3 = const_vec3!([0.0, 1.0, 0.0]);
+
+/// How far below a seat to look for something moving it could be mounted on
+const MOUNT_CHECK_DISTANCE: f32 = 0.5;
+
+/// A finished seat
+#[derive(Component)]
+pub struct Seat {
+    pub occupant: Option<Entity>,
+}
+
+/// Added to the player while they're sitting on the seat
+#[derive(Component)]
+pub struct Seated(pub Entity);
+
+/// A building that was built on something that moves (a vehicle) and moves along with it
+#[derive(Component)]
+pub struct MountedOn {
+    pub entity: Entity,
+    /// The building's transform relative to `entity`
+    pub offset: Transform,
+}
+
+/// Makes finished seats usable and mounts them on whatever moving body they were built on
+pub fn init_seats(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    rapier_context: Res<RapierContext>,
+
+    transform_query: Query<&Transform>,
+    children_query: Query<&Children>,
+    parent_query: Query<&Parent>,
+    body_query: Query<&RigidBody>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::Seat {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert(Seat { occupant: None })
+            .insert(Interactable::new("prompt.sit"));
+
+        let seat_transform = match transform_query.get(event.entity) {
+            Ok(e) => *e,
+            Err(_) => continue,
+        };
+        // The collider is the first child
+        let own_collider = match children_query.get(event.entity).map(|e| e.first()) {
+            Ok(Some(e)) => *e,
+            _ => continue,
+        };
+
+        let hit = rapier_context.cast_ray(
+            seat_transform.translation + seat_transform.up() * 0.1,
+            -seat_transform.up(),
+            MOUNT_CHECK_DISTANCE,
+            true,
+            CollisionLayers::MOUNT_RAY.interaction_groups(),
+            Some(&|e| e != own_collider),
+        );
+
+        // Building colliders are children of the building, vehicles have theirs on themselves
+        let mount = match hit {
+            Some((entity, _)) => parent_query.get(entity).map_or(entity, |e| e.0),
+            None => continue,
+        };
+
+        let moves = matches!(
+            body_query.get(mount),
+            Ok(RigidBody::Dynamic)
+                | Ok(RigidBody::KinematicPositionBased)
+                | Ok(RigidBody::KinematicVelocityBased)
+        );
+        if !moves {
+            continue;
+        }
+
+        let mount_transform = match transform_query.get(mount) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        commands.entity(event.entity).insert(MountedOn {
+            entity: mount,
+            offset: Transform::from_matrix(
+                mount_transform.compute_matrix().inverse() * seat_transform.compute_matrix(),
+            ),
+        });
+    }
+}
+
+/// Moves mounted buildings (and their collider) along with what they're mounted on
+pub fn follow_mounts(
+    mut commands: Commands,
+
+    mounted_query: Query<(Entity, &MountedOn, &Children, &BuildingReferenceComponent)>,
+    mut transform_query: Query<&mut Transform>,
+) {
+    for (entity, mounted, children, building_ref) in mounted_query.iter() {
+        let mount_transform = match transform_query.get(mounted.entity) {
+            Ok(e) => *e,
+            // It stays where it was if what it was on is gone
+            Err(_) => {
+                commands.entity(entity).remove::<MountedOn>();
+                continue;
+            }
+        };
+
+        let new_transform = mount_transform.mul_transform(mounted.offset);
+        match transform_query.get_mut(entity) {
+            Ok(mut transform) => *transform = new_transform,
+            Err(_) => continue,
+        }
+
+        // Building colliders use the same transform as the building, plus the offset
+        if let Ok(mut collider_transform) = transform_query.get_mut(children[0]) {
+            *collider_transform =
+                new_transform.with_add_translation(building_ref.0.shape_data.collider_offset);
+        }
+    }
+}
+
+/// Sits the player down on the seat they used, if nobody else is on it
+pub fn use_seats(
+    mut commands: Commands,
+    mut interact_events: EventReader<InteractEvent>,
+
+    mut player_query: Query<(Entity, &mut Velocity), With<Player>>,
+    mut seat_query: Query<&mut Seat>,
+) {
+    for event in interact_events.iter() {
+        let mut seat = match seat_query.get_mut(event.entity) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+        if seat.occupant.is_some() {
+            continue;
+        }
+
+        let (player_entity, mut velocity) = match player_query.get_single_mut() {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+
+        *velocity = Velocity::default();
+        seat.occupant = Some(player_entity);
+
+        commands
+            .entity(player_entity)
+            .insert(Seated(event.entity))
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(*NO_COLLISION);
+    }
+}
+
+/// `Action::Interact` stands the player up in front of their seat
+pub fn leave_seats(
+    mut commands: Commands,
+    input: ActionInput,
+
+    mut player_query: Query<(Entity, &Seated, &mut Transform), Without<Seat>>,
+    mut seat_query: Query<(&mut Seat, &Transform)>,
+) {
+    if !input.just_pressed(Action::Interact) {
+        return;
+    }
+
+    let (player_entity, seated, mut player_transform) = match player_query.get_single_mut() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    if let Ok((mut seat, seat_transform)) = seat_query.get_mut(seated.0) {
+        seat.occupant = None;
+        player_transform.translation = seat_transform
+            .with_add_translation(*SEAT_EXIT_OFFSET)
+            .translation;
+    }
+
+    stand_up(&mut commands, player_entity);
+}
+
+/// Keeps seated players on their seat, wherever it goes
+pub fn carry_seated(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &Seated, &mut Transform), Without<Seat>>,
+    seat_query: Query<&Transform, With<Seat>>,
+) {
+    for (entity, seated, mut transform) in player_query.iter_mut() {
+        match seat_query.get(seated.0) {
+            Ok(seat_transform) => {
+                transform.translation = seat_transform.with_add_translation(SIT_OFFSET).translation;
+            }
+            // The seat is gone, so give control back to the player where they are
+            Err(_) => stand_up(&mut commands, entity),
+        }
+    }
+}
+
+fn stand_up(commands: &mut Commands, player: Entity) {
+    commands
+        .entity(player)
+        .remove::<Seated>()
+        .insert(RigidBody::Dynamic)
+        .insert(*PLAYER_COLLISION);
+}
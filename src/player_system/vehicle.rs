@@ -0,0 +1,266 @@
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use crate::constants::{
+    NO_COLLISION, PLAYER_COLLISION, VEHICLE_EXIT_OFFSET, VEHICLE_SEAT_OFFSET, VEHICLE_TURN_SPEED,
+};
+
+use super::{
+    input::{Action, ActionInput},
+    interaction::InteractEvent,
+    player::{movement_input, CameraComp, Player},
+};
+
+/// A vehicle made by a garage
+#[derive(Component)]
+pub struct Vehicle {
+    pub speed: f32,
+}
+
+/// Added to the player while they're driving the vehicle
+#[derive(Component)]
+pub struct Driving(pub Entity);
+
+/// Gets the player in the vehicle they used
+pub fn enter_vehicle(
+    mut commands: Commands,
+    mut interact_events: EventReader<InteractEvent>,
+
+    mut player_query: Query<(Entity, &mut Velocity), (With<Player>, Without<Vehicle>)>,
+    vehicle_query: Query<&Vehicle>,
+) {
+    for event in interact_events.iter() {
+        if !vehicle_query.contains(event.entity) {
+            continue;
+        }
+
+        let (player_entity, mut player_velocity) = match player_query.get_single_mut() {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+
+        *player_velocity = Velocity::default();
+
+        commands
+            .entity(player_entity)
+            .insert(Driving(event.entity))
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(*NO_COLLISION);
+    }
+}
+
+/// `Action::Interact` gets out of the vehicle being driven
+pub fn exit_vehicle(
+    mut commands: Commands,
+    input: ActionInput,
+
+    mut player_query: Query<
+        (Entity, &Driving, &mut Transform, &mut Velocity),
+        (With<Player>, Without<Vehicle>),
+    >,
+    mut vehicle_query: Query<(&Transform, &mut Velocity), With<Vehicle>>,
+) {
+    if !input.just_pressed(Action::Interact) {
+        return;
+    }
+
+    let (player_entity, driving, mut player_transform, mut player_velocity) =
+        match player_query.get_single_mut() {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+
+    if let Ok((vehicle_transform, mut vehicle_velocity)) = vehicle_query.get_mut(driving.0) {
+        player_transform.translation =
+            vehicle_transform.translation + vehicle_transform.rotation * *VEHICLE_EXIT_OFFSET;
+
+        // Don't let an abandoned vehicle roll off on its own
+        vehicle_velocity.linvel = Vec3::new(0.0, vehicle_velocity.linvel.y, 0.0);
+        vehicle_velocity.angvel = Vec3::ZERO;
+    }
+
+    *player_velocity = Velocity::default();
+
+    commands
+        .entity(player_entity)
+        .remove::<Driving>()
+        .insert(RigidBody::Dynamic)
+        .insert(*PLAYER_COLLISION);
+}
+
+/// Moves the driver along with their vehicle
+pub fn carry_driver(
+    mut commands: Commands,
+    mut player_query: Query<(Entity, &Driving, &mut Transform), Without<Vehicle>>,
+    vehicle_query: Query<&Transform, With<Vehicle>>,
+) {
+    for (entity, driving, mut transform) in player_query.iter_mut() {
+        match vehicle_query.get(driving.0) {
+            Ok(vehicle_transform) => {
+                transform.translation = vehicle_transform.translation + *VEHICLE_SEAT_OFFSET;
+            }
+            // The vehicle is gone, so give control back to the player where they are
+            Err(_) => {
+                commands
+                    .entity(entity)
+                    .remove::<Driving>()
+                    .insert(RigidBody::Dynamic)
+                    .insert(*PLAYER_COLLISION);
+            }
+        }
+    }
+}
+
+/// Drives the vehicle the player is in with the same controls the player moves with
+pub fn drive_vehicles(
+    input: ActionInput,
+
+    c_query: Query<&CameraComp>,
+    p_query: Query<&Driving, With<Player>>,
+    mut v_query: Query<(&Vehicle, &Transform, &mut Velocity)>,
+) {
+    let driving = match p_query.get_single() {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    let (vehicle, transform, mut velocity) = match v_query.get_mut(driving.0) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+    let camera = c_query.single();
+
+    let mov = movement_input(&input, camera);
+
+    velocity.linvel.x = mov.x * vehicle.speed;
+    velocity.linvel.z = mov.y * vehicle.speed;
+
+    if mov.length() == 0.0 {
+        velocity.angvel = Vec3::ZERO;
+        return;
+    }
+
+    // Turn the front (-Z) of the vehicle towards where it's going
+    let forward = transform.forward();
+    let current = (-forward.x).atan2(-forward.z);
+    let target = (-mov.x).atan2(-mov.y);
+
+    let mut diff = target - current;
+    if diff > PI {
+        diff -= 2.0 * PI;
+    } else if diff < -PI {
+        diff += 2.0 * PI;
+    }
+
+    velocity.angvel = Vec3::new(0.0, diff * VEHICLE_TURN_SPEED, 0.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::ecs::event::Events;
+
+    use super::super::input::{Binding, InputBindings, ScrollInput};
+    use super::*;
+
+    fn headless_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<InputBindings>()
+            .init_resource::<Input<KeyCode>>()
+            .init_resource::<Input<MouseButton>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<Input<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<ScrollInput>()
+            .add_event::<InteractEvent>()
+            .add_system(enter_vehicle)
+            .add_system(exit_vehicle)
+            .add_system(carry_driver.before(exit_vehicle));
+        app
+    }
+
+    #[test]
+    fn players_get_in_and_out_of_vehicles() {
+        let mut app = headless_app();
+
+        let player = app
+            .world
+            .spawn()
+            .insert_bundle((
+                Player {
+                    name: String::new(),
+                    speed: 1.0,
+                },
+                Transform::default(),
+                Velocity {
+                    linvel: Vec3::X,
+                    ..default()
+                },
+                RigidBody::Dynamic,
+                *PLAYER_COLLISION,
+            ))
+            .id();
+        let vehicle_transform = Transform::from_xyz(10.0, 0.0, 0.0);
+        let vehicle = app
+            .world
+            .spawn()
+            .insert_bundle((
+                Vehicle { speed: 1.0 },
+                vehicle_transform,
+                Velocity::default(),
+            ))
+            .id();
+
+        app.world
+            .get_resource_mut::<Events<InteractEvent>>()
+            .unwrap()
+            .send(InteractEvent { entity: vehicle });
+        app.update();
+
+        assert_eq!(app.world.get::<Driving>(player).unwrap().0, vehicle);
+        assert_eq!(
+            app.world.get::<RigidBody>(player),
+            Some(&RigidBody::KinematicPositionBased)
+        );
+        assert_eq!(
+            app.world.get::<CollisionGroups>(player),
+            Some(&*NO_COLLISION)
+        );
+        assert_eq!(
+            app.world.get::<Velocity>(player),
+            Some(&Velocity::default())
+        );
+
+        // The driver rides along
+        app.update();
+        assert_eq!(
+            app.world.get::<Transform>(player).unwrap().translation,
+            vehicle_transform.translation + *VEHICLE_SEAT_OFFSET
+        );
+
+        let key = match InputBindings::default().bindings(Action::Interact)[0] {
+            Binding::Key(e) => e,
+            e => panic!("interact is bound to {:?}", e),
+        };
+        app.world
+            .get_resource_mut::<Input<KeyCode>>()
+            .unwrap()
+            .press(key);
+        app.update();
+
+        assert!(app.world.get::<Driving>(player).is_none());
+        assert_eq!(
+            app.world.get::<RigidBody>(player),
+            Some(&RigidBody::Dynamic)
+        );
+        assert_eq!(
+            app.world.get::<CollisionGroups>(player),
+            Some(&*PLAYER_COLLISION)
+        );
+        assert_eq!(
+            app.world.get::<Transform>(player).unwrap().translation,
+            vehicle_transform.translation + *VEHICLE_EXIT_OFFSET
+        );
+    }
+}
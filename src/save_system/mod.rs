@@ -0,0 +1,26 @@
+use bevy::prelude::*;
+
+use crate::building_system::buildings::building_init_done;
+
+use self::world_save::{
+    load_world, read_world_save, request_world_save, save_world, SaveWorldEvent,
+};
+
+pub mod world_save;
+
+pub struct SaveSystemPlugin;
+
+impl Plugin for SaveSystemPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SaveWorldEvent>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, read_world_save)
+            .add_system_set_to_stage(
+                CoreStage::PreUpdate,
+                SystemSet::new()
+                    .with_run_criteria(building_init_done)
+                    .with_system(load_world),
+            )
+            .add_system(request_world_save)
+            .add_system(save_world.after(request_world_save));
+    }
+}
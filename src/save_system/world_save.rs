@@ -0,0 +1,910 @@
+use std::{path::Path, sync::Arc};
+
+use bevy::{pbr::NotShadowCaster, prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent,
+        building::{transform_between_points, Pipe},
+        building_components::*,
+        building_functions::MoveTransform,
+        buildings::{Building, BuildingArcs, BuildingReferenceComponent, BuildingType},
+        health::BuildingHealth,
+        BlueprintFillMaterial, MaterialHandles, PipeCylinderMaterial,
+    },
+    collision_layers::CollisionLayers,
+    constants::BLUEPRINT_COLLISION,
+    iridium_system::{
+        storage::IridiumStorage,
+        transceiver::{NextTransceiverId, Transceiver},
+    },
+    player_system::input::{Action, ActionInput},
+    settings::GameSettings,
+    terrain_generation_system::generator::TerrainSeed,
+};
+
+/// Everything needed to rebuild a world, written to `GameSettings::save_path` as RON
+#[derive(Serialize, Deserialize, Debug)]
+pub struct WorldSave {
+    pub seed: u32,
+    pub buildings: Vec<SavedBuilding>,
+    /// `NextTransceiverId`, so transceivers built after loading don't reuse the id of a saved one
+    pub next_transceiver_id: u32,
+}
+
+/// A placed building or blueprint
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedBuilding {
+    pub building_type: BuildingType,
+    /// The building itself, or both ends (`Pipe.pt_1` and `Pipe.pt_2`) of a two-point building
+    pub ends: Vec<SavedEnd>,
+    /// `None` once it's finished
+    pub blueprint: Option<SavedBlueprint>,
+    /// What's left of the building's health and the iridium it stores, `None` for blueprints and
+    /// buildings that don't have any
+    pub health: Option<u32>,
+    pub iridium: Option<u32>,
+    /// Its id and partner, the pairs are kept by id
+    pub transceiver: Option<Transceiver>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedEnd {
+    pub transform: SavedTransform,
+    /// The `BuildingRotation` of its collider
+    pub rotation: f32,
+    pub snapped_to: Option<SavedPart>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SavedBlueprint {
+    pub current: u32,
+    pub cost: u32,
+}
+
+/// What a `SnappedTo` points at, `end` is 0 for single buildings and 0 or 1 for two-point ones
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SavedPart {
+    pub building: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct SavedTransform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl From<Transform> for SavedTransform {
+    fn from(transform: Transform) -> Self {
+        SavedTransform {
+            translation: transform.translation,
+            rotation: transform.rotation,
+            scale: transform.scale,
+        }
+    }
+}
+
+impl From<SavedTransform> for Transform {
+    fn from(transform: SavedTransform) -> Self {
+        Transform {
+            translation: transform.translation,
+            rotation: transform.rotation,
+            scale: transform.scale,
+        }
+    }
+}
+
+/// A save read at startup, its buildings are spawned once the buildings are loaded
+pub struct PendingWorldLoad(pub WorldSave);
+
+/// Reads the save at `GameSettings::save_path` if `GameSettings::load_save` is set, the terrain is
+/// generated from its seed
+///
+/// Runs before every other startup system, so the player spawns on the saved terrain
+pub fn read_world_save(mut commands: Commands, settings: Res<GameSettings>) {
+    if !settings.load_save {
+        return;
+    }
+
+    let path = &settings.save_path;
+    let bytes = match std::fs::read(path) {
+        Ok(e) => e,
+        Err(e) => {
+            info!("{:?}: {}, starting a new world", path, e);
+            return;
+        }
+    };
+
+    match ron::de::from_bytes::<WorldSave>(&bytes) {
+        Ok(save) => {
+            commands.insert_resource(TerrainSeed(save.seed));
+            commands.insert_resource(PendingWorldLoad(save));
+        }
+        Err(e) => error!(
+            "{:?}: {}, starting a new world (saving will overwrite it)",
+            path, e
+        ),
+    }
+}
+
+/// Spawns the buildings of the save read by `read_world_save`, the same way placing them would
+///
+/// Finished buildings are spawned finished with their saved health, iridium and transceiver pair,
+/// and get their `BuildingCompletedEvent` here. The init systems keep what's already there
+pub fn load_world(
+    mut commands: Commands,
+    save: Option<Res<PendingWorldLoad>>,
+
+    building_arcs: Res<BuildingArcs>,
+    asset_server: Res<AssetServer>,
+    bp_material_handles: Res<MaterialHandles>,
+    bp_fill_materials: Res<BlueprintFillMaterial>,
+    pipe_cylinder_material: Res<PipeCylinderMaterial>,
+    mut next_transceiver_id: ResMut<NextTransceiverId>,
+    mut completed_events: EventWriter<BuildingCompletedEvent>,
+) {
+    let save = match save {
+        Some(e) => e,
+        None => return,
+    };
+    commands.remove_resource::<PendingWorldLoad>();
+
+    // The entity of each end, to point `SnappedTo` at
+    let mut spawned: Vec<Vec<Entity>> = Vec::with_capacity(save.0.buildings.len());
+    let mut next_id = save.0.next_transceiver_id;
+
+    for (i, saved) in save.0.buildings.iter().enumerate() {
+        let building = match building_arcs.0.get(&saved.building_type) {
+            Some(e) => e.clone(),
+            None => {
+                error!(
+                    "building {}: {:?} doesn't exist anymore, skipping it",
+                    i, saved.building_type
+                );
+                spawned.push(Vec::new());
+                continue;
+            }
+        };
+
+        let ends = if building.segment_data.is_some() {
+            2
+        } else {
+            1
+        };
+        if saved.ends.len() != ends {
+            error!(
+                "building {}: {:?} has {} ends instead of {}, skipping it",
+                i,
+                saved.building_type,
+                saved.ends.len(),
+                ends
+            );
+            spawned.push(Vec::new());
+            continue;
+        }
+
+        let (entity, ends) = match building.segment_data {
+            Some(_) => spawn_segment(
+                &mut commands,
+                &building,
+                saved,
+                &asset_server,
+                &bp_fill_materials,
+                &pipe_cylinder_material,
+            ),
+            None => {
+                let entity = spawn_single(&mut commands, &building, saved, &bp_material_handles);
+                (entity, vec![entity])
+            }
+        };
+        spawned.push(ends);
+
+        if saved.blueprint.is_some() {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        if let Some(health) = saved.health {
+            entity_commands.insert(BuildingHealth {
+                current: health.min(building.health),
+                max: building.health,
+                pending: 0,
+            });
+        }
+        if let (Some(current), Some(capacity)) = (
+            saved.iridium,
+            building.iridium_data.storage.filter(|e| *e > 0),
+        ) {
+            entity_commands.insert(IridiumStorage {
+                current: current.min(capacity),
+                capacity,
+            });
+        }
+        if let Some(transceiver) = &saved.transceiver {
+            next_id = next_id.max(transceiver.id + 1);
+            entity_commands.insert(transceiver.clone());
+        }
+
+        completed_events.send(BuildingCompletedEvent { entity, building });
+    }
+    // Transceivers finished from now on get ids none of the saved ones use
+    next_transceiver_id.0 = next_id;
+
+    for (saved, entities) in save.0.buildings.iter().zip(spawned.iter()) {
+        for (end, entity) in saved.ends.iter().zip(entities.iter()) {
+            let target = end
+                .snapped_to
+                .and_then(|e| spawned.get(e.building).and_then(|b| b.get(e.end)));
+
+            if let Some(target) = target {
+                commands.entity(*entity).insert(SnappedTo(*target));
+            }
+        }
+    }
+
+    info!(
+        "loaded {} buildings (seed {})",
+        spawned.iter().filter(|e| !e.is_empty()).count(),
+        save.0.seed
+    );
+}
+
+/// Same entities as a placed blueprint (see `check_cursor_bp_collision`), finished ones the way
+/// `apply_blueprint_progress` leaves them
+fn spawn_single(
+    commands: &mut Commands,
+    building: &Arc<Building>,
+    saved: &SavedBuilding,
+    bp_material_handles: &MaterialHandles,
+) -> Entity {
+    let end = &saved.ends[0];
+    let transform = Transform::from(end.transform);
+
+    let (material, groups) = match saved.blueprint {
+        Some(_) => (
+            bp_material_handles.for_obstruction(Obstruction::None),
+            *BLUEPRINT_COLLISION,
+        ),
+        None => (
+            building.shape_data.material.clone().unwrap(),
+            CollisionLayers::FINISHED_BUILDING.groups(),
+        ),
+    };
+
+    let mut entity_commands = commands.spawn_bundle(PbrBundle {
+        mesh: building.shape_data.mesh.clone().unwrap(),
+        material,
+        transform,
+        ..Default::default()
+    });
+    entity_commands.insert(BuildingReferenceComponent(building.clone()));
+    if let Some(e) = saved.blueprint {
+        entity_commands.insert_bundle((
+            NotShadowCaster,
+            PlacedBlueprint {
+                cost: e.cost,
+                current: e.current,
+            },
+        ));
+    }
+
+    entity_commands
+        .with_children(|parent| {
+            parent.spawn_bundle((
+                building.shape_data.collider.clone(),
+                groups,
+                transform.with_add_translation(building.shape_data.collider_offset),
+                Sensor(false),
+                ActiveCollisionTypes::all(),
+                BuildingRotation(end.rotation),
+            ));
+        })
+        .id()
+}
+
+/// Same entities as a placed two-point building, finished ones the way `apply_segment_progress` leaves them
+fn spawn_segment(
+    commands: &mut Commands,
+    building: &Arc<Building>,
+    saved: &SavedBuilding,
+    asset_server: &AssetServer,
+    bp_fill_materials: &BlueprintFillMaterial,
+    pipe_cylinder_material: &PipeCylinderMaterial,
+) -> (Entity, Vec<Entity>) {
+    let segment = building.segment_data.as_ref().unwrap();
+    let pt_1 = Transform::from(saved.ends[0].transform);
+    let pt_2 = Transform::from(saved.ends[1].transform);
+
+    let mut piece_transform = transform_between_points(
+        pt_1.with_add_translation(segment.offset).translation,
+        pt_2.with_add_translation(segment.offset).translation,
+    );
+    piece_transform.scale.y = piece_transform.scale.y.max(0.001);
+
+    let is_pipe = building.building_id.building_type == BuildingType::Pipe;
+    let (piece_material, end_material, groups) = match saved.blueprint {
+        Some(e) => {
+            let material = bp_fill_materials.get_bp_fill_material(e.current, e.cost);
+            (material.clone(), material, *BLUEPRINT_COLLISION)
+        }
+        None => {
+            let material = building.shape_data.material.clone().unwrap();
+            let piece_material = match is_pipe {
+                true => pipe_cylinder_material.0.clone(),
+                false => material.clone(),
+            };
+            (
+                piece_material,
+                material,
+                CollisionLayers::FINISHED_BUILDING.groups(),
+            )
+        }
+    };
+
+    let mut root = commands.spawn_bundle((
+        GlobalTransform::identity(),
+        Transform::default(),
+        BuildingReferenceComponent(building.clone()),
+        Pipe::new(pt_1, pt_2),
+    ));
+    if let Some(e) = saved.blueprint {
+        root.insert(SegmentBlueprint {
+            cost: e.cost,
+            current: e.current,
+        });
+    }
+
+    let mut ends = Vec::with_capacity(2);
+    root.with_children(|parent| {
+        let mut piece = parent.spawn_bundle(PbrBundle {
+            mesh: asset_server.load(&segment.mesh_path),
+            material: piece_material,
+            transform: piece_transform,
+            ..Default::default()
+        });
+        if saved.blueprint.is_some() {
+            piece.insert(SegmentPiece);
+        }
+        piece.with_children(|parent| {
+            parent.spawn_bundle((
+                piece_transform,
+                segment.collider.clone(),
+                groups,
+                Sensor(false),
+                NotShadowCaster,
+            ));
+        });
+
+        for (i, (end, transform)) in saved.ends.iter().zip([pt_1, pt_2]).enumerate() {
+            let mut end_commands = parent.spawn_bundle(PbrBundle {
+                mesh: building.shape_data.mesh.clone().unwrap(),
+                material: end_material.clone(),
+                transform,
+                ..Default::default()
+            });
+            end_commands.insert_bundle((
+                NotShadowCaster,
+                BuildingReferenceComponent(building.clone()),
+            ));
+
+            match (saved.blueprint.is_some(), i) {
+                (true, 0) => {
+                    end_commands.insert(SegmentFirst);
+                }
+                (true, _) => {
+                    end_commands.insert(SegmentSecond);
+                }
+                (false, _) if is_pipe => {
+                    end_commands.insert(BuiltPipeEnd);
+                }
+                (false, _) => (),
+            }
+
+            end_commands.with_children(|parent| {
+                parent.spawn_bundle((
+                    building.shape_data.collider.clone(),
+                    transform.with_add_translation(building.shape_data.collider_offset),
+                    groups,
+                    Sensor(false),
+                    BuildingRotation(end.rotation),
+                ));
+            });
+            ends.push(end_commands.id());
+        }
+    });
+
+    (root.id(), ends)
+}
+
+/// Sent to write the world to `GameSettings::save_path`
+pub struct SaveWorldEvent;
+
+/// Sends a `SaveWorldEvent` when `Action::SaveWorld` is pressed
+pub fn request_world_save(input: ActionInput, mut save_events: EventWriter<SaveWorldEvent>) {
+    if input.just_pressed(Action::SaveWorld) {
+        save_events.send(SaveWorldEvent);
+    }
+}
+
+/// Writes every placed building and blueprint to `GameSettings::save_path` on `SaveWorldEvent`
+pub fn save_world(
+    mut save_events: EventReader<SaveWorldEvent>,
+    settings: Res<GameSettings>,
+    seed: Res<TerrainSeed>,
+    next_transceiver_id: Res<NextTransceiverId>,
+    pending: Option<Res<PendingWorldLoad>>,
+
+    building_query: Query<
+        (
+            Entity,
+            &Transform,
+            &BuildingReferenceComponent,
+            Option<&PlacedBlueprint>,
+            Option<&SegmentBlueprint>,
+            Option<&Pipe>,
+            Option<&BuildingHealth>,
+            Option<&IridiumStorage>,
+            Option<&Transceiver>,
+        ),
+        (Without<Parent>, Without<CursorBp>, Without<SegmentPreview>),
+    >,
+    end_query: Query<(), With<BuildingReferenceComponent>>,
+    children_query: Query<&Children>,
+    rotation_query: Query<&BuildingRotation>,
+    snapped_query: Query<&SnappedTo>,
+) {
+    if save_events.iter().count() == 0 {
+        return;
+    }
+    if pending.is_some() {
+        warn!("the save hasn't been loaded yet, not overwriting it");
+        return;
+    }
+
+    // Every building with the entity and transform of each of its ends
+    let mut placed = Vec::new();
+    for (
+        entity,
+        transform,
+        building_ref,
+        placed_bp,
+        segment_bp,
+        pipe,
+        health,
+        storage,
+        transceiver,
+    ) in building_query.iter()
+    {
+        let building = &building_ref.0;
+
+        let ends = match (&building.segment_data, pipe) {
+            (None, _) => vec![(entity, *transform)],
+            (Some(_), Some(pipe)) => {
+                // The ends are the children with a building reference, in the order they were placed
+                let ends: Vec<Entity> = children_query.get(entity).map_or(Vec::new(), |e| {
+                    e.iter()
+                        .copied()
+                        .filter(|e| end_query.contains(*e))
+                        .collect()
+                });
+                if ends.len() != 2 {
+                    warn!("{:?} doesn't have 2 ends, not saving it", building_ref);
+                    continue;
+                }
+                vec![(ends[0], pipe.pt_1), (ends[1], pipe.pt_2)]
+            }
+            (Some(_), None) => {
+                warn!("{:?} doesn't have a `Pipe`, not saving it", building_ref);
+                continue;
+            }
+        };
+
+        let blueprint = match (placed_bp, segment_bp) {
+            (Some(e), _) => Some(SavedBlueprint {
+                current: e.current,
+                cost: e.cost,
+            }),
+            (_, Some(e)) => Some(SavedBlueprint {
+                current: e.current,
+                cost: e.cost,
+            }),
+            (None, None) => None,
+        };
+
+        // The ends are filled in once every building has been gone through, they can point at any of them
+        let saved = SavedBuilding {
+            building_type: building.building_id.building_type.clone(),
+            ends: Vec::new(),
+            blueprint,
+            health: health.map(|e| e.current),
+            iridium: storage.map(|e| e.current),
+            transceiver: transceiver.cloned(),
+        };
+        placed.push((ends, saved));
+    }
+
+    let mut parts = HashMap::default();
+    for (i, (ends, _)) in placed.iter().enumerate() {
+        for (j, (entity, _)) in ends.iter().enumerate() {
+            parts.insert(
+                *entity,
+                SavedPart {
+                    building: i,
+                    end: j,
+                },
+            );
+        }
+    }
+
+    let buildings = placed
+        .into_iter()
+        .map(|(ends, saved)| SavedBuilding {
+            ends: ends
+                .into_iter()
+                .map(|(entity, transform)| SavedEnd {
+                    transform: transform.into(),
+                    // The collider is the first child
+                    rotation: children_query
+                        .get(entity)
+                        .ok()
+                        .and_then(|e| rotation_query.get(e[0]).ok())
+                        .map_or(0.0, |e| e.0),
+                    snapped_to: snapped_query
+                        .get(entity)
+                        .ok()
+                        .and_then(|e| parts.get(&e.0).copied()),
+                })
+                .collect(),
+            ..saved
+        })
+        .collect::<Vec<_>>();
+
+    let save = WorldSave {
+        seed: seed.0,
+        buildings,
+        next_transceiver_id: next_transceiver_id.0,
+    };
+
+    let path = Path::new(&settings.save_path);
+    let text = match ron::ser::to_string_pretty(&save, PrettyConfig::default()) {
+        Ok(e) => e,
+        Err(e) => {
+            error!("Could not save the world: {}", e);
+            return;
+        }
+    };
+    let written = match path.parent() {
+        Some(e) if !e.as_os_str().is_empty() => std::fs::create_dir_all(e),
+        _ => Ok(()),
+    }
+    .and_then(|()| std::fs::write(path, text));
+
+    match written {
+        Ok(()) => info!(
+            "{:?}: saved {} buildings",
+            settings.save_path,
+            save.buildings.len()
+        ),
+        Err(e) => error!("{:?}: could not save the world: {}", settings.save_path, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{asset::AssetPlugin, ecs::event::Events};
+
+    use crate::{
+        building_system::{
+            building_system_startup,
+            buildings::{
+                BuildingIO, BuildingId, BuildingIridiumData, BuildingSegmentData,
+                BuildingShapeData, BuildingSnapData,
+            },
+            health::init_building_health,
+        },
+        iridium_system::{storage::init_iridium_storage, transceiver::init_transceivers},
+        localization::Localization,
+        structure_system::keep::{mark_keeps, Keep},
+    };
+
+    use super::*;
+
+    // Indices into `world_save().buildings`
+    const WELLPUMP: usize = 0;
+    const TRANSCEIVER: usize = 2;
+
+    fn building(
+        building_type: BuildingType,
+        storage: Option<u32>,
+        segment_data: Option<BuildingSegmentData>,
+        mesh: &Handle<Mesh>,
+        material: &Handle<StandardMaterial>,
+    ) -> Arc<Building> {
+        Arc::new(Building {
+            building_id: BuildingId {
+                building_name: format!("{:?}", building_type),
+                building_type,
+            },
+            health: 100,
+            iridium_data: BuildingIridiumData {
+                io: BuildingIO::None,
+                storage,
+                current: None,
+                generation: None,
+                cost: 100,
+            },
+            shape_data: BuildingShapeData {
+                mesh: Some(mesh.clone()),
+                material: Some(material.clone()),
+                path: String::new(),
+                collider: Collider::cuboid(1.0, 1.0, 1.0),
+                collider_offset: Vec3::ZERO,
+            },
+            snap_data: BuildingSnapData {
+                buildings: Vec::new(),
+                transform: Vec::new(),
+                rotation_allowed: Vec::new(),
+            },
+            segment_data,
+        })
+    }
+
+    /// An app with only what loading and saving need, reading and writing the save at `path`
+    fn headless_app(path: &str) -> App {
+        let mut app = App::new();
+        app.add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<StandardMaterial>();
+
+        let mesh = app
+            .world
+            .get_resource_mut::<Assets<Mesh>>()
+            .unwrap()
+            .add(Mesh::from(shape::Cube { size: 1.0 }));
+        let material = app
+            .world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap()
+            .add(StandardMaterial::default());
+
+        let pipe_segment = BuildingSegmentData {
+            mesh_path: "models/pipe_segment.glb".to_string(),
+            collider: Collider::cylinder(0.5, 0.2),
+            offset: Vec3::ZERO,
+            end_offset: Vec3::ZERO,
+            cost_per_unit: 0,
+            max_length: None,
+            max_slope: None,
+        };
+        let arcs = [
+            building(BuildingType::Wellpump, Some(500), None, &mesh, &material),
+            building(BuildingType::ShieldGenerator, None, None, &mesh, &material),
+            building(BuildingType::Transceiver, None, None, &mesh, &material),
+            building(BuildingType::Keep, None, None, &mesh, &material),
+            building(
+                BuildingType::Pipe,
+                None,
+                Some(pipe_segment),
+                &mesh,
+                &material,
+            ),
+        ]
+        .into_iter()
+        .map(|e| (e.building_id.building_type.clone(), e))
+        .collect();
+
+        app.insert_resource(BuildingArcs(arcs))
+            .insert_resource(GameSettings {
+                save_path: path.to_string(),
+                load_save: true,
+                ..default()
+            })
+            .init_resource::<Localization>()
+            .insert_resource(TerrainSeed(0))
+            .init_resource::<NextTransceiverId>()
+            .add_event::<BuildingCompletedEvent>()
+            .add_event::<SaveWorldEvent>()
+            .add_startup_system_to_stage(StartupStage::PreStartup, read_world_save)
+            .add_startup_system(building_system_startup)
+            .add_system_to_stage(CoreStage::PreUpdate, load_world)
+            .add_system(init_building_health)
+            .add_system(init_iridium_storage)
+            .add_system(init_transceivers)
+            .add_system(mark_keeps)
+            .add_system(save_world);
+        app
+    }
+
+    fn end(x: f32, snapped_to: Option<SavedPart>) -> SavedEnd {
+        SavedEnd {
+            transform: Transform::from_xyz(x, 0.0, 0.0).into(),
+            rotation: 0.0,
+            snapped_to,
+        }
+    }
+
+    fn finished(building_type: BuildingType, ends: Vec<SavedEnd>) -> SavedBuilding {
+        SavedBuilding {
+            building_type,
+            ends,
+            blueprint: None,
+            health: None,
+            iridium: None,
+            transceiver: None,
+        }
+    }
+
+    fn transceiver(id: u32, partner: u32) -> Transceiver {
+        Transceiver {
+            id,
+            partner: Some(partner),
+            powered: false,
+        }
+    }
+
+    /// A wellpump with some damage and iridium, a shield blueprint, a transceiver pair, a keep and a
+    /// pipe from the wellpump to one of the transceivers
+    fn world_save() -> WorldSave {
+        let part = |building| SavedPart { building, end: 0 };
+
+        WorldSave {
+            seed: 1234,
+            buildings: vec![
+                SavedBuilding {
+                    health: Some(60),
+                    iridium: Some(200),
+                    ..finished(BuildingType::Wellpump, vec![end(0.0, None)])
+                },
+                SavedBuilding {
+                    blueprint: Some(SavedBlueprint {
+                        current: 30,
+                        cost: 100,
+                    }),
+                    ..finished(BuildingType::ShieldGenerator, vec![end(10.0, None)])
+                },
+                SavedBuilding {
+                    transceiver: Some(transceiver(3, 5)),
+                    ..finished(BuildingType::Transceiver, vec![end(20.0, None)])
+                },
+                SavedBuilding {
+                    transceiver: Some(transceiver(5, 3)),
+                    ..finished(BuildingType::Transceiver, vec![end(30.0, None)])
+                },
+                finished(BuildingType::Keep, vec![end(40.0, None)]),
+                finished(
+                    BuildingType::Pipe,
+                    vec![
+                        end(0.0, Some(part(WELLPUMP))),
+                        end(20.0, Some(part(TRANSCEIVER))),
+                    ],
+                ),
+            ],
+            next_transceiver_id: 7,
+        }
+    }
+
+    /// Every building, keyed by its type and the X of its first end
+    fn buildings(app: &mut App) -> Vec<(BuildingType, f32, Entity)> {
+        let mut query = app.world.query_filtered::<(
+            Entity,
+            &Transform,
+            &BuildingReferenceComponent,
+            Option<&Pipe>,
+        ), Without<Parent>>();
+
+        query
+            .iter(&app.world)
+            .map(|(entity, transform, building_ref, pipe)| {
+                let x = pipe.map_or(transform.translation.x, |e| e.pt_1.translation.x);
+                (building_ref.0.building_id.building_type.clone(), x, entity)
+            })
+            .collect()
+    }
+
+    fn find(
+        buildings: &[(BuildingType, f32, Entity)],
+        building_type: BuildingType,
+        x: f32,
+    ) -> Entity {
+        buildings
+            .iter()
+            .find(|e| e.0 == building_type && e.1 == x)
+            .unwrap_or_else(|| panic!("no {:?} at {}", building_type, x))
+            .2
+    }
+
+    fn assert_loaded(app: &mut App) {
+        let buildings = buildings(app);
+        assert_eq!(buildings.len(), 6);
+        let world = &app.world;
+
+        let wellpump = find(&buildings, BuildingType::Wellpump, 0.0);
+        assert!(world.get::<PlacedBlueprint>(wellpump).is_none());
+        let health = world.get::<BuildingHealth>(wellpump).unwrap();
+        assert_eq!((health.current, health.max), (60, 100));
+        let storage = world.get::<IridiumStorage>(wellpump).unwrap();
+        assert_eq!((storage.current, storage.capacity), (200, 500));
+
+        let shield = find(&buildings, BuildingType::ShieldGenerator, 10.0);
+        let blueprint = world.get::<PlacedBlueprint>(shield).unwrap();
+        assert_eq!((blueprint.current, blueprint.cost), (30, 100));
+        assert!(world.get::<BuildingHealth>(shield).is_none());
+
+        let first = find(&buildings, BuildingType::Transceiver, 20.0);
+        let second = find(&buildings, BuildingType::Transceiver, 30.0);
+        let pair = |entity| {
+            let transceiver = world.get::<Transceiver>(entity).unwrap();
+            (transceiver.id, transceiver.partner)
+        };
+        assert_eq!(pair(first), (3, Some(5)));
+        assert_eq!(pair(second), (5, Some(3)));
+        assert_eq!(world.get_resource::<NextTransceiverId>().unwrap().0, 7);
+
+        // The territory is back without the keep being a blueprint first
+        let keep = find(&buildings, BuildingType::Keep, 40.0);
+        assert!(world.get::<Keep>(keep).is_some());
+
+        let pipe = find(&buildings, BuildingType::Pipe, 0.0);
+        assert!(world.get::<SegmentBlueprint>(pipe).is_none());
+        let snapped: Vec<Entity> = world
+            .get::<Children>(pipe)
+            .unwrap()
+            .iter()
+            .filter(|e| world.get::<BuildingReferenceComponent>(**e).is_some())
+            .map(|e| world.get::<SnappedTo>(*e).unwrap().0)
+            .collect();
+        assert_eq!(snapped, vec![wellpump, first]);
+    }
+
+    #[test]
+    fn saved_world_loads_the_same() {
+        let path = std::env::temp_dir().join(format!("solstice_world_{}.ron", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let text = ron::ser::to_string_pretty(&world_save(), PrettyConfig::default()).unwrap();
+        std::fs::write(path, text).unwrap();
+
+        // Load the hand-written save and save it again
+        let mut app = headless_app(path);
+        app.update();
+        assert_loaded(&mut app);
+        app.world
+            .get_resource_mut::<Events<SaveWorldEvent>>()
+            .unwrap()
+            .send(SaveWorldEvent);
+        app.update();
+
+        // Then load what that wrote in a fresh app
+        let mut app = headless_app(path);
+        app.update();
+        app.update();
+        assert_eq!(app.world.get_resource::<TerrainSeed>().unwrap().0, 1234);
+        assert_loaded(&mut app);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn transceiver_power_isnt_saved() {
+        let saved = SavedBuilding {
+            transceiver: Some(Transceiver {
+                powered: true,
+                ..transceiver(1, 2)
+            }),
+            ..finished(BuildingType::Transceiver, vec![end(0.0, None)])
+        };
+
+        let text = ron::ser::to_string(&saved).unwrap();
+        let loaded: SavedBuilding = ron::de::from_str(&text).unwrap();
+        let transceiver = loaded.transceiver.unwrap();
+        assert_eq!((transceiver.id, transceiver.partner), (1, Some(2)));
+        // Pairs pay their upkeep again before they relay anything
+        assert!(!transceiver.powered);
+    }
+}
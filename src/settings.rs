@@ -0,0 +1,238 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Where the settings are read from at startup, settings it leaves out keep their defaults
+pub const GAME_SETTINGS_PATH: &str = "config/settings.ron";
+
+/// Gameplay tunables, read from `GAME_SETTINGS_PATH` at startup so they can be changed without
+/// recompiling
+///
+/// Iridium amounts are in hundredths, same as building costs, except for `fabricator_speed`
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameSettings {
+    /// Reload building and terrain block definitions when their files are edited
+    pub watch_definitions: bool,
+    /// String table the game's text is read from (`assets/locale/<language>.lang`), anything it's
+    /// missing is in English
+    pub language: String,
+    /// Where the world is saved to and loaded from
+    pub save_path: String,
+    /// Load the world from `save_path` at startup if there's a save there
+    pub load_save: bool,
+
+    /// Half the size of the area the sun casts shadows in
+    pub half_size: f32,
+
+    /// How much iridium the portafab puts into a blueprint every second, in whole iridium
+    pub fabricator_speed: u32,
+    /// How many times a second blueprints are filled
+    pub blueprint_steps_per_second: u32,
+    /// How close the cursor has to be to a socket to snap to it
+    pub snap_distance: f32,
+    /// How far from the keep other buildings can be placed
+    pub keep_build_radius: f32,
+
+    pub player_speed: f32,
+    /// How fast players go up and down ladders
+    pub climb_speed: f32,
+    /// How close the player has to be to something to use it (get in a vehicle, sit on a seat...)
+    pub interact_distance: f32,
+    /// Players that fall below this respawn
+    pub kill_plane_height: f32,
+
+    /// Radius of the dome a shield generator projects
+    pub shield_radius: f32,
+    /// Iridium it costs a shield to absorb one point of damage
+    pub shield_iridium_per_damage: u32,
+    /// Iridium a collapsed shield's network needs before the shield comes back up
+    pub shield_restart_iridium: u32,
+
+    /// Max number of jobs waiting in an arsenal
+    pub arsenal_queue_size: usize,
+
+    /// Iridium it costs a garage to make one vehicle
+    pub vehicle_cost: u32,
+    pub vehicle_speed: f32,
+
+    /// Most iridium a transceiver pair moves between its networks every second
+    pub transceiver_throughput: u32,
+    /// Iridium each side of a transceiver pair pays every second to stay linked
+    pub transceiver_upkeep: u32,
+
+    /// Iridium a spatial anchor jump costs per unit of distance
+    pub anchor_cost_per_unit: u32,
+    /// Seconds before either anchor of a jump can be used again
+    pub anchor_cooldown: f32,
+
+    /// Iridium it costs a S.S.I.M. to make one drone
+    pub drone_cost: u32,
+    /// Max number of drones a single S.S.I.M. can have
+    pub drones_per_ssim: usize,
+    /// Iridium a drone carries each trip
+    pub drone_carry: u32,
+    pub drone_speed: f32,
+    /// How high above the terrain drones try to stay
+    pub drone_cruise_height: f32,
+    /// How far ahead drones look for terrain to avoid
+    pub drone_lookahead: f32,
+
+    /// How much of the way a gate opens/closes every second
+    pub gate_speed: f32,
+
+    /// Impulses a boost pad can be set to, using the pad cycles through them
+    pub boost_pad_strengths: Vec<f32>,
+    /// Seconds before the same body can be boosted again
+    pub boost_pad_cooldown: f32,
+}
+
+impl Default for GameSettings {
+    fn default() -> Self {
+        GameSettings {
+            watch_definitions: true,
+            language: "en".to_string(),
+            save_path: "saves/world.ron".to_string(),
+            load_save: true,
+
+            half_size: 10.0,
+
+            fabricator_speed: 100,
+            blueprint_steps_per_second: 30,
+            snap_distance: 0.5,
+            keep_build_radius: 60.0,
+
+            player_speed: 5.0,
+            climb_speed: 3.0,
+            interact_distance: 4.0,
+            kill_plane_height: 50.0,
+
+            shield_radius: 8.0,
+            shield_iridium_per_damage: 10,
+            shield_restart_iridium: 20_00,
+
+            arsenal_queue_size: 5,
+
+            vehicle_cost: 80_00,
+            vehicle_speed: 12.0,
+
+            transceiver_throughput: 5_00,
+            transceiver_upkeep: 50,
+
+            anchor_cost_per_unit: 10,
+            anchor_cooldown: 10.0,
+
+            drone_cost: 60_00,
+            drones_per_ssim: 4,
+            drone_carry: 10_00,
+            drone_speed: 8.0,
+            drone_cruise_height: 3.0,
+            drone_lookahead: 4.0,
+
+            gate_speed: 1.5,
+
+            boost_pad_strengths: vec![10.0, 20.0, 35.0],
+            boost_pad_cooldown: 1.0,
+        }
+    }
+}
+
+impl GameSettings {
+    /// Reads the settings file, logs and keeps the defaults if it's missing or invalid
+    pub fn load(path: &str) -> Self {
+        let bytes = match std::fs::read(path) {
+            Ok(e) => e,
+            Err(e) => {
+                info!("{:?}: {}, using the default settings", path, e);
+                return GameSettings::default();
+            }
+        };
+
+        let settings = match ron::de::from_bytes::<GameSettings>(&bytes) {
+            Ok(e) => e,
+            Err(e) => {
+                error!("{:?}: {}, using the default settings", path, e);
+                return GameSettings::default();
+            }
+        };
+
+        match settings.validate() {
+            Ok(()) => settings,
+            Err(e) => {
+                error!("{:?}: {}, using the default settings", path, e);
+                GameSettings::default()
+            }
+        }
+    }
+
+    /// Checks everything the types can't, the error names the field that's wrong
+    pub fn validate(&self) -> Result<(), String> {
+        if self.blueprint_steps_per_second == 0 {
+            return Err("field `blueprint_steps_per_second`: must be more than 0".to_string());
+        }
+        if self.shield_iridium_per_damage == 0 {
+            return Err("field `shield_iridium_per_damage`: must be more than 0".to_string());
+        }
+        if self.drone_cruise_height <= 0.0 {
+            return Err("field `drone_cruise_height`: must be more than 0".to_string());
+        }
+        if self.boost_pad_strengths.is_empty() {
+            return Err("field `boost_pad_strengths`: needs at least one strength".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Iridium the portafab puts into a blueprint every blueprint step
+    pub fn fabricator_per_step(&self) -> u32 {
+        self.fabricator_speed.saturating_mul(100) / self.blueprint_steps_per_second
+    }
+}
+
+/// Starts watching asset files for edits if `GameSettings::watch_definitions` is set
+///
+/// Runs before every other startup system, only files loaded after this are watched
+pub fn watch_definition_files(settings: Res<GameSettings>, asset_server: Res<AssetServer>) {
+    if !settings.watch_definitions {
+        return;
+    }
+
+    match asset_server.watch_for_changes() {
+        Ok(()) => info!("watching definition files for edits"),
+        Err(e) => error!("Could not watch definition files for edits: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_are_valid() {
+        assert_eq!(GameSettings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn shipped_settings_match_the_defaults() {
+        let text = include_str!("../config/settings.ron");
+        let settings = ron::de::from_str::<GameSettings>(text).unwrap();
+
+        assert_eq!(settings.validate(), Ok(()));
+        assert_eq!(
+            format!("{:?}", settings),
+            format!("{:?}", GameSettings::default())
+        );
+    }
+
+    #[test]
+    fn fabricator_per_step_saturates() {
+        let settings = GameSettings {
+            fabricator_speed: u32::MAX,
+            ..GameSettings::default()
+        };
+
+        assert_eq!(
+            settings.fabricator_per_step(),
+            u32::MAX / settings.blueprint_steps_per_second
+        );
+    }
+}
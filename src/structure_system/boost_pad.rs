@@ -0,0 +1,157 @@
+use bevy::{math::const_vec3, prelude::*, utils::HashMap};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, buildings::BuildingType, raycasting::BuildCursor,
+    },
+    collision_layers::CollisionLayers,
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput},
+    },
+    settings::GameSettings,
+};
+
+/// Where the pad's sensor is relative to the pad, just above the plate
+const SENSOR_OFFSET: Vec3 = const_vec3!([0.0, 0.3, 0.0]);
+
+/// A finished boost pad
+#[derive(Component)]
+pub struct BoostPad {
+    /// Index into `GameSettings::boost_pad_strengths`
+    pub strength: usize,
+}
+
+impl BoostPad {
+    pub fn impulse(&self, settings: &GameSettings) -> f32 {
+        let strengths = &settings.boost_pad_strengths;
+        strengths[self.strength % strengths.len()]
+    }
+}
+
+/// The sensor on top of a boost pad, its parent is the pad
+#[derive(Component)]
+pub struct BoostPadSensor;
+
+/// When each body was last boosted (in seconds since startup), so it isn't launched every frame
+#[derive(Default)]
+pub struct BoostCooldowns(HashMap<Entity, f64>);
+
+/// Gives finished boost pads their sensor
+pub fn spawn_boost_pad_sensors(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::BoostPad {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert(BoostPad { strength: 0 })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(TransformBundle::from_transform(
+                        Transform::from_translation(SENSOR_OFFSET),
+                    ))
+                    .insert_bundle((
+                        Collider::cuboid(0.9, 0.15, 0.9),
+                        Sensor(true),
+                        CollisionLayers::FINISHED_BUILDING.groups(),
+                        ActiveEvents::COLLISION_EVENTS,
+                        BoostPadSensor,
+                    ));
+            });
+    }
+}
+
+/// Launches dynamic bodies that start touching a boost pad along the pad's up direction
+pub fn boost_bodies(
+    mut commands: Commands,
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    mut cooldowns: ResMut<BoostCooldowns>,
+    mut collision_events: EventReader<CollisionEvent>,
+
+    sensor_query: Query<&Parent, With<BoostPadSensor>>,
+    pad_query: Query<(&BoostPad, &Transform)>,
+    body_query: Query<&RigidBody>,
+    parent_query: Query<&Parent>,
+    mut impulse_query: Query<&mut ExternalImpulse>,
+) {
+    let now = time.seconds_since_startup();
+    cooldowns
+        .0
+        .retain(|_, last| now - *last < settings.boost_pad_cooldown as f64);
+
+    for event in collision_events.iter() {
+        let (a, b) = match event {
+            CollisionEvent::Started(a, b, _) => (*a, *b),
+            CollisionEvent::Stopped(..) => continue,
+        };
+
+        let (pad, other) = match (sensor_query.get(a), sensor_query.get(b)) {
+            (Ok(pad), _) => (pad.0, b),
+            (_, Ok(pad)) => (pad.0, a),
+            _ => continue,
+        };
+
+        // The collider is either on the body itself (player, vehicles) or a child of it
+        let body = match body_query.get(other) {
+            Ok(_) => other,
+            Err(_) => match parent_query.get(other) {
+                Ok(e) => e.0,
+                Err(_) => continue,
+            },
+        };
+        if body_query.get(body) != Ok(&RigidBody::Dynamic) || cooldowns.0.contains_key(&body) {
+            continue;
+        }
+
+        let (boost_pad, pad_transform) = match pad_query.get(pad) {
+            Ok(e) => e,
+            Err(_) => continue,
+        };
+
+        let impulse = pad_transform.up() * boost_pad.impulse(&settings);
+        match impulse_query.get_mut(body) {
+            Ok(mut e) => e.impulse += impulse,
+            Err(_) => {
+                commands.entity(body).insert(ExternalImpulse {
+                    impulse,
+                    torque_impulse: Vec3::ZERO,
+                });
+            }
+        }
+
+        cooldowns.0.insert(body, now);
+    }
+}
+
+/// With the cursor over a boost pad, F switches to the next strength
+pub fn boost_pad_input(
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+    settings: Res<GameSettings>,
+
+    parent_query: Query<&Parent>,
+    mut pad_query: Query<&mut BoostPad>,
+) {
+    if selected_building.id.is_some() || !input.just_pressed(Action::UseBuilding) {
+        return;
+    }
+
+    let mut pad = match build_cursor
+        .hovered_entity(&parent_query)
+        .and_then(|e| pad_query.get_mut(e).ok())
+    {
+        Some(e) => e,
+        None => return,
+    };
+
+    pad.strength = (pad.strength + 1) % settings.boost_pad_strengths.len();
+    info!("boost pad strength set to {}", pad.impulse(&settings));
+}
@@ -0,0 +1,142 @@
+use bevy::{math::const_vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, buildings::BuildingType, raycasting::BuildCursor,
+    },
+    collision_layers::CollisionLayers,
+    constants::GATE_OPEN_COLLISION,
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput},
+    },
+    settings::GameSettings,
+};
+
+/// Where the door is relative to the gate when it's closed
+const DOOR_CLOSED: Vec3 = const_vec3!([0.0, 1.55, 0.0]);
+/// How far up the door slides when the gate opens
+const DOOR_TRAVEL: f32 = 2.9;
+
+/// A finished gate, the door is one of its children
+#[derive(Component)]
+pub struct Gate {
+    pub open: bool,
+}
+
+/// The part of a gate that moves
+#[derive(Component)]
+pub struct GateDoor {
+    /// 0 is closed, 1 is open
+    pub progress: f32,
+}
+
+pub struct GateAssets {
+    door_mesh: Handle<Mesh>,
+}
+
+pub fn gate_startup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GateAssets {
+        door_mesh: asset_server.load("models/buildings/gate_door.obj"),
+    });
+}
+
+/// Gives finished gates their door, gates start closed
+pub fn spawn_gate_doors(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    gate_assets: Res<GateAssets>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::Gate {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert(Gate { open: false })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(PbrBundle {
+                        mesh: gate_assets.door_mesh.clone(),
+                        material: event.building.shape_data.material.clone().unwrap(),
+                        transform: Transform::from_translation(DOOR_CLOSED),
+                        ..Default::default()
+                    })
+                    .insert_bundle((
+                        Collider::cuboid(1.9, 1.45, 0.15),
+                        CollisionLayers::FINISHED_BUILDING.groups(),
+                        GateDoor { progress: 0.0 },
+                    ));
+            });
+    }
+}
+
+/// With the cursor over a gate, F opens or closes it
+///
+/// An open door stops colliding with players (and their vehicles) straight away, a closing one blocks them
+/// straight away, the mesh catches up in `animate_gates`. There's only one team for now, so everyone is on
+/// the owner's side
+pub fn toggle_gates(
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+
+    parent_query: Query<&Parent>,
+    children_query: Query<&Children>,
+    mut gate_query: Query<&mut Gate>,
+    mut door_query: Query<&mut CollisionGroups, With<GateDoor>>,
+) {
+    if selected_building.id.is_some() || !input.just_pressed(Action::UseBuilding) {
+        return;
+    }
+
+    let gate_entity = match build_cursor.hovered_entity(&parent_query) {
+        Some(e) if gate_query.contains(e) => e,
+        _ => return,
+    };
+
+    let mut gate = gate_query.get_mut(gate_entity).unwrap();
+    gate.open = !gate.open;
+
+    for child in children_query.get(gate_entity).unwrap().iter() {
+        if let Ok(mut groups) = door_query.get_mut(*child) {
+            *groups = match gate.open {
+                true => *GATE_OPEN_COLLISION,
+                false => CollisionLayers::FINISHED_BUILDING.groups(),
+            };
+        }
+    }
+}
+
+/// Slides gate doors towards their open/closed position
+pub fn animate_gates(
+    time: Res<Time>,
+    settings: Res<GameSettings>,
+    gate_query: Query<&Gate>,
+    mut door_query: Query<(&Parent, &mut GateDoor, &mut Transform)>,
+) {
+    for (parent, mut door, mut transform) in door_query.iter_mut() {
+        let open = match gate_query.get(parent.0) {
+            Ok(e) => e.open,
+            Err(_) => continue,
+        };
+
+        let goal = match open {
+            true => 1.0,
+            false => 0.0,
+        };
+        if door.progress == goal {
+            continue;
+        }
+
+        let step = settings.gate_speed * time.delta_seconds();
+        door.progress = match open {
+            true => (door.progress + step).min(1.0),
+            false => (door.progress - step).max(0.0),
+        };
+
+        transform.translation = DOOR_CLOSED + Vec3::Y * (DOOR_TRAVEL * door.progress);
+    }
+}
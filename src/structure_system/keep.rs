@@ -0,0 +1,91 @@
+use bevy::prelude::*;
+
+use crate::building_system::{
+    blueprint::BuildingCompletedEvent,
+    building_components::{Obstruction, PlacedBlueprint},
+    buildings::{BuildingReferenceComponent, BuildingType},
+};
+
+/// A keep, placed or finished
+///
+/// Everything else has to be built within `GameSettings::keep_build_radius` of it. There's only one
+/// team for now, so there can only be one keep
+#[derive(Component)]
+pub struct Keep;
+
+/// Marks keeps as soon as their blueprint is placed so the territory exists right away, and finished
+/// keeps that never were blueprints (loaded from a save)
+pub fn mark_keeps(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    placed_query: Query<(Entity, &BuildingReferenceComponent), Added<PlacedBlueprint>>,
+) {
+    let placed = placed_query
+        .iter()
+        .filter(|(_, building_ref)| building_ref.0.building_id.building_type == BuildingType::Keep)
+        .map(|(entity, _)| entity);
+    let completed = completed_events
+        .iter()
+        .filter(|event| event.building.building_id.building_type == BuildingType::Keep)
+        .map(|event| event.entity);
+
+    for entity in placed.chain(completed) {
+        commands.entity(entity).insert(Keep);
+    }
+}
+
+/// Checks if a `building_type` can go at `point` as far as the territory is concerned, the territory
+/// is everything within `radius` of one of the `keeps`
+pub fn territory_obstruction(
+    building_type: &BuildingType,
+    point: Vec3,
+    radius: f32,
+    keeps: &[Vec3],
+) -> Obstruction {
+    if *building_type == BuildingType::Keep {
+        return match keeps.is_empty() {
+            true => Obstruction::None,
+            false => Obstruction::Limit,
+        };
+    }
+
+    match keeps.iter().any(|e| e.distance(point) <= radius) {
+        true => Obstruction::None,
+        false => Obstruction::OutOfTerritory,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RADIUS: f32 = 10.0;
+
+    #[test]
+    fn only_one_keep() {
+        assert_eq!(
+            territory_obstruction(&BuildingType::Keep, Vec3::ZERO, RADIUS, &[]),
+            Obstruction::None
+        );
+        // Even inside the territory of the first one
+        assert_eq!(
+            territory_obstruction(&BuildingType::Keep, Vec3::ZERO, RADIUS, &[Vec3::X]),
+            Obstruction::Limit
+        );
+    }
+
+    #[test]
+    fn buildings_need_a_keep_in_range() {
+        let keeps = [Vec3::ZERO, Vec3::new(100.0, 0.0, 0.0)];
+        let at =
+            |x| territory_obstruction(&BuildingType::Wall, Vec3::new(x, 0.0, 0.0), RADIUS, &keeps);
+
+        assert_eq!(at(RADIUS), Obstruction::None);
+        assert_eq!(at(95.0), Obstruction::None);
+        assert_eq!(at(RADIUS + 0.1), Obstruction::OutOfTerritory);
+        assert_eq!(
+            territory_obstruction(&BuildingType::Wall, Vec3::ZERO, RADIUS, &[]),
+            Obstruction::OutOfTerritory
+        );
+    }
+}
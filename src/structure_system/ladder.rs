@@ -0,0 +1,74 @@
+use bevy::{math::const_vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{blueprint::BuildingCompletedEvent, buildings::BuildingType},
+    collision_layers::CollisionLayers,
+    player_system::player::{MovementMode, Player},
+};
+
+/// Where the climbable volume is relative to the ladder, in front of the rungs
+const VOLUME_OFFSET: Vec3 = const_vec3!([0.0, 1.6, 0.35]);
+
+/// The sensor in front of a finished ladder, players inside it climb
+#[derive(Component)]
+pub struct LadderVolume;
+
+/// Gives finished ladders their climbable volume
+pub fn spawn_ladder_volumes(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+) {
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::LadderBlock {
+            continue;
+        }
+
+        commands.entity(event.entity).with_children(|parent| {
+            parent
+                .spawn_bundle(TransformBundle::from_transform(
+                    Transform::from_translation(VOLUME_OFFSET),
+                ))
+                .insert_bundle((
+                    Collider::cuboid(0.5, 1.6, 0.3),
+                    Sensor(true),
+                    CollisionLayers::FINISHED_BUILDING.groups(),
+                    LadderVolume,
+                ));
+        });
+    }
+}
+
+/// Switches players between walking and climbing as they go in and out of ladder volumes
+///
+/// Climbing players don't fall, `player_movement_system` moves them up and down instead
+pub fn update_movement_mode(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+
+    mut player_query: Query<(Entity, &mut MovementMode), With<Player>>,
+    volume_query: Query<&LadderVolume>,
+) {
+    for (entity, mut mode) in player_query.iter_mut() {
+        let on_ladder = rapier_context
+            .intersections_with(entity)
+            .any(|(a, b, intersecting)| {
+                let other = if a == entity { b } else { a };
+                intersecting && volume_query.contains(other)
+            });
+
+        let new_mode = match on_ladder {
+            true => MovementMode::Climbing,
+            false => MovementMode::Walking,
+        };
+        if *mode == new_mode {
+            continue;
+        }
+
+        *mode = new_mode;
+        commands.entity(entity).insert(GravityScale(match new_mode {
+            MovementMode::Climbing => 0.0,
+            MovementMode::Walking => 1.0,
+        }));
+    }
+}
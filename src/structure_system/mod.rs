@@ -0,0 +1,37 @@
+use bevy::prelude::{App, Plugin};
+
+use self::{
+    boost_pad::{boost_bodies, boost_pad_input, spawn_boost_pad_sensors, BoostCooldowns},
+    gate::{animate_gates, gate_startup, spawn_gate_doors, toggle_gates},
+    keep::mark_keeps,
+    ladder::{spawn_ladder_volumes, update_movement_mode},
+    spatial_anchor::{anchor_input, init_anchors, update_anchor_prompts, use_anchors},
+};
+
+pub mod boost_pad;
+pub mod gate;
+pub mod keep;
+pub mod ladder;
+pub mod spatial_anchor;
+
+pub struct StructureSystemPlugin;
+
+impl Plugin for StructureSystemPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoostCooldowns>()
+            .add_startup_system(gate_startup)
+            .add_system(spawn_gate_doors)
+            .add_system(toggle_gates)
+            .add_system(animate_gates)
+            .add_system(mark_keeps)
+            .add_system(spawn_ladder_volumes)
+            .add_system(update_movement_mode)
+            .add_system(spawn_boost_pad_sensors)
+            .add_system(boost_bodies)
+            .add_system(boost_pad_input)
+            .add_system(init_anchors)
+            .add_system(anchor_input)
+            .add_system(update_anchor_prompts)
+            .add_system(use_anchors);
+    }
+}
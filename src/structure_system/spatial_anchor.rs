@@ -0,0 +1,259 @@
+use bevy::{math::const_vec3, prelude::*};
+use bevy_rapier3d::prelude::*;
+
+use crate::{
+    building_system::{
+        blueprint::BuildingCompletedEvent, building_components::IsColliding,
+        buildings::BuildingType, raycasting::BuildCursor,
+    },
+    constants::ANCHOR_ARRIVAL_COLLISION,
+    iridium_system::{network::IridiumNetworks, storage::IridiumStorage},
+    player_system::{
+        gui_system::gui_startup::SelectedBuilding,
+        input::{Action, ActionInput, InputBindings},
+        interaction::{InteractEvent, Interactable},
+        player::Player,
+        seat::Seated,
+        vehicle::Driving,
+    },
+    settings::GameSettings,
+};
+
+/// Where players arrive, relative to the anchor
+const ARRIVAL_OFFSET: Vec3 = const_vec3!([0.0, 1.0, 0.0]);
+
+/// A finished spatial anchor
+///
+/// There's only one team for now, so every anchor belongs to the player
+#[derive(Component)]
+pub struct SpatialAnchor {
+    /// Anchors are numbered in the order they're built
+    pub number: u32,
+    /// Where using this anchor takes the player
+    pub destination: Option<Entity>,
+    /// Seconds since startup when the anchor can be used (or arrived at) again
+    pub ready_at: f64,
+}
+
+/// The sensor over an anchor where players arrive, jumps are refused while something is in it
+#[derive(Component)]
+pub struct AnchorArrival;
+
+/// Iridium it costs to jump between two points
+pub fn jump_cost(from: Vec3, to: Vec3, settings: &GameSettings) -> u32 {
+    (from.distance(to) * settings.anchor_cost_per_unit as f32) as u32
+}
+
+/// Numbers finished anchors and gives them their arrival sensor
+pub fn init_anchors(
+    mut commands: Commands,
+    mut completed_events: EventReader<BuildingCompletedEvent>,
+    anchor_query: Query<&SpatialAnchor>,
+) {
+    let mut number = anchor_query.iter().map(|e| e.number + 1).max().unwrap_or(1);
+
+    for event in completed_events.iter() {
+        if event.building.building_id.building_type != BuildingType::SpatialAnchor {
+            continue;
+        }
+
+        commands
+            .entity(event.entity)
+            .insert_bundle((
+                SpatialAnchor {
+                    number,
+                    destination: None,
+                    ready_at: 0.0,
+                },
+                Interactable::new("prompt.jump"),
+            ))
+            .with_children(|parent| {
+                // Same kind of sensor as the cursor blueprint so the overlap test is the same one
+                parent
+                    .spawn_bundle(TransformBundle::from_transform(
+                        Transform::from_translation(ARRIVAL_OFFSET),
+                    ))
+                    .insert_bundle((
+                        Collider::cuboid(0.5, 0.5, 0.5),
+                        Sensor(true),
+                        ActiveCollisionTypes::all(),
+                        *ANCHOR_ARRIVAL_COLLISION,
+                        AnchorArrival,
+                    ));
+            });
+        number += 1;
+    }
+}
+
+/// With the cursor over an anchor, F switches its destination to the next anchor
+pub fn anchor_input(
+    input: ActionInput,
+    build_cursor: Res<BuildCursor>,
+    selected_building: Res<SelectedBuilding>,
+
+    parent_query: Query<&Parent>,
+    mut anchor_query: Query<(Entity, &mut SpatialAnchor)>,
+) {
+    if selected_building.id.is_some() || !input.just_pressed(Action::UseBuilding) {
+        return;
+    }
+
+    let entity = match build_cursor.hovered_entity(&parent_query) {
+        Some(e) if anchor_query.contains(e) => e,
+        _ => return,
+    };
+
+    let mut others: Vec<(u32, Entity)> = anchor_query
+        .iter()
+        .filter(|(e, _)| *e != entity)
+        .map(|(e, anchor)| (anchor.number, e))
+        .collect();
+    others.sort_unstable();
+
+    let mut anchor = anchor_query.get_mut(entity).unwrap().1;
+    let current = anchor
+        .destination
+        .and_then(|destination| others.iter().position(|(_, e)| *e == destination));
+
+    anchor.destination = match current {
+        Some(i) if i + 1 < others.len() => Some(others[i + 1].1),
+        Some(_) => None,
+        None => others.first().map(|(_, e)| *e),
+    };
+}
+
+/// Keeps the "Press E to ..." text of anchors showing where they go and what it costs
+pub fn update_anchor_prompts(
+    input_bindings: Res<InputBindings>,
+    settings: Res<GameSettings>,
+    mut anchor_query: Query<(&SpatialAnchor, &Transform, &mut Interactable)>,
+    destination_query: Query<(&SpatialAnchor, &Transform)>,
+) {
+    for (anchor, transform, mut interactable) in anchor_query.iter_mut() {
+        let (prompt, args) = match anchor
+            .destination
+            .and_then(|e| destination_query.get(e).ok())
+        {
+            Some((destination, destination_transform)) => {
+                let cost = jump_cost(
+                    transform.translation,
+                    destination_transform.translation,
+                    &settings,
+                ) as f32
+                    / 100.0;
+                (
+                    "prompt.jump_to_anchor",
+                    vec![
+                        ("number", destination.number.to_string()),
+                        ("cost", format!("{:.1}", cost)),
+                    ],
+                )
+            }
+            None => (
+                "prompt.pick_destination",
+                vec![("key", input_bindings.label(Action::UseBuilding))],
+            ),
+        };
+
+        if interactable.prompt != prompt || interactable.args != args {
+            interactable.prompt = prompt.to_string();
+            interactable.args = args;
+        }
+    }
+}
+
+/// Teleports the player to the destination of the anchor they used
+///
+/// Refused if the player is driving or seated, either anchor is cooling down, something is in the
+/// way at the destination or the anchor's network can't pay for the distance
+pub fn use_anchors(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    networks: Res<IridiumNetworks>,
+    settings: Res<GameSettings>,
+    mut interact_events: EventReader<InteractEvent>,
+
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut Velocity,
+            Option<&Driving>,
+            Option<&Seated>,
+        ),
+        With<Player>,
+    >,
+    mut anchor_query: Query<(&mut SpatialAnchor, &Transform, &Children), Without<Player>>,
+    arrival_query: Query<&AnchorArrival>,
+    mut storage_query: Query<&mut IridiumStorage>,
+) {
+    let now = time.seconds_since_startup();
+
+    for event in interact_events.iter() {
+        let (from, destination) = match anchor_query.get(event.entity) {
+            Ok((anchor, transform, _)) => (transform.translation, anchor.destination),
+            Err(_) => continue,
+        };
+
+        let destination = match destination {
+            Some(e) => e,
+            None => {
+                info!("anchor has no destination");
+                continue;
+            }
+        };
+        let (to, arrival) = match anchor_query.get(destination) {
+            Ok((_, transform, children)) => (
+                transform.translation,
+                children
+                    .iter()
+                    .copied()
+                    .find(|e| arrival_query.contains(*e)),
+            ),
+            Err(_) => {
+                // It was destroyed
+                anchor_query.get_mut(event.entity).unwrap().0.destination = None;
+                continue;
+            }
+        };
+
+        // The carry systems would put a mounted player back, or leave the vehicle without a driver
+        if let Ok((_, _, driving, seated)) = player_query.get_single() {
+            if driving.is_some() || seated.is_some() {
+                info!("can't jump while driving or seated");
+                continue;
+            }
+        }
+
+        if [event.entity, destination]
+            .iter()
+            .any(|e| anchor_query.get(*e).unwrap().0.ready_at > now)
+        {
+            info!("anchor is cooling down");
+            continue;
+        }
+
+        if arrival.map_or(true, |e| e.is_intersecting(&rapier_context)) {
+            info!("something is in the way at the destination anchor");
+            continue;
+        }
+
+        let cost = jump_cost(from, to, &settings);
+        if networks.available(&storage_query, event.entity) < cost {
+            info!("not enough iridium to jump");
+            continue;
+        }
+        networks.withdraw(&mut storage_query, event.entity, cost);
+
+        let (mut player_transform, mut velocity, _, _) = match player_query.get_single_mut() {
+            Ok(e) => e,
+            Err(_) => return,
+        };
+        player_transform.translation = to + ARRIVAL_OFFSET;
+        *velocity = Velocity::default();
+
+        for entity in [event.entity, destination] {
+            anchor_query.get_mut(entity).unwrap().0.ready_at =
+                now + settings.anchor_cooldown as f64;
+        }
+    }
+}
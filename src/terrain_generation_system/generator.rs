@@ -6,17 +6,19 @@ use bevy::gltf::GltfPrimitive;
 use bevy::utils::HashMap;
 use bevy::{gltf::GltfMesh, prelude::*};
 
-use bevy_rapier3d::prelude::{ActiveCollisionTypes, Collider, CollisionGroups};
+use bevy_rapier3d::prelude::ActiveCollisionTypes;
 
-use rand::{prelude::ThreadRng, Rng};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 
 use noise::{NoiseFn, Perlin, Seedable};
 
 use crate::building_system::buildings::InsertNoReturn;
+use crate::building_system::load_models::ModelManifest;
+use crate::collision_layers::{CollisionLayer, CollisionLayers};
 use crate::model_loader::{combine_gltf_mesh, translate_gltf_primitives};
-use crate::terrain_generation_system::terrain_block::{Blocks, TerrainBlockData, TerrainBlockName};
-use crate::{
-    constants::SEED, terrain_generation_system::compound_collider_builder::CompoundColliderBuilder,
+use crate::terrain_generation_system::terrain_block::{
+    terrain_blocks_loaded, Blocks, TerrainBlockDefinition, TerrainBlockHandles, TerrainBlockName,
+    TerrainBlockNames, GROUND_TAG, SPIRE_TAG, WELL_TAG,
 };
 
 #[derive(Component)]
@@ -25,6 +27,55 @@ pub struct GeneratorOptions {
     pub height: u32,
 }
 
+/// Seed of the terrain noise, random unless a world save sets it
+pub struct TerrainSeed(pub u32);
+
+/// Column the island is centered on
+const CENTER: u32 = 50;
+/// Columns this far (squared) from the center or further stay empty
+const ISLAND_RADIUS_SQUARED: i32 = 2500;
+/// Layer the top of the ground is on, spires stand on it and deeper ground goes under it
+const GROUND_LEVEL: usize = 50;
+
+/// The noise that decides where the ground and spires are, `generate_terrain` and
+/// `default_spawn_point` both go through it so they agree on the terrain's shape
+struct TerrainNoise {
+    ground: Perlin,
+    spires: Perlin,
+}
+
+impl TerrainNoise {
+    fn new(seed: &TerrainSeed) -> Self {
+        TerrainNoise {
+            ground: Perlin::default().set_seed(seed.0),
+            spires: Perlin::default().set_seed(seed.0 / 2),
+        }
+    }
+
+    /// How far (squared) the column is from the center of the island
+    fn distance_squared(i: u32, j: u32) -> i32 {
+        (i as i32 - CENTER as i32).pow(2) + (j as i32 - CENTER as i32).pow(2)
+    }
+
+    /// How deep the ground is at the column, there's only ground where it's above 0
+    fn ground(&self, i: u32, j: u32) -> Option<f64> {
+        if TerrainNoise::distance_squared(i, j) >= ISLAND_RADIUS_SQUARED {
+            return None;
+        }
+
+        let n = self.ground.get([(i as f64) * 0.15, (j as f64) * 0.15]);
+        match n > 0.0 {
+            true => Some(n),
+            false => None,
+        }
+    }
+
+    /// Whether a spire stands on the column, if it has ground
+    fn spire(&self, i: u32, j: u32) -> bool {
+        self.spires.get([(i as f64) * 0.1, (j as f64) * 0.1]) > 0.5
+    }
+}
+
 pub struct TerrainGenDone {
     pub done: bool,
 }
@@ -37,120 +88,55 @@ pub fn generate_terrain(
     gltf_meshes: Res<Assets<GltfMesh>>,
     mut images: ResMut<Assets<Image>>,
     mut done: ResMut<TerrainGenDone>,
+    model_manifest: Res<ModelManifest>,
+    block_handles: Res<TerrainBlockHandles>,
+    block_definitions: Res<Assets<TerrainBlockDefinition>>,
 
     generator_options: Res<GeneratorOptions>,
+    seed: Res<TerrainSeed>,
 ) {
-    if done.done {
+    if done.done
+        || !terrain_blocks_loaded(
+            &asset_server,
+            &block_handles,
+            &block_definitions,
+            &model_manifest,
+        )
+        || !model_manifest.is_done()
+    {
         return;
     }
 
     let time = Instant::now();
 
-    let perlin = Perlin::default().set_seed(*SEED);
-    let spire_perlin = Perlin::default().set_seed(*SEED / 2);
-
-    let z_cuboid = Collider::cuboid(0.25, 0.25, 1.5);
-    let y_cuboid = Collider::cuboid(0.25, 1.5, 0.25);
-    let x_cuboid = Collider::cuboid(1.5, 0.25, 0.25);
-    let q = Quat::default();
-
-    let hollowground_collider_vec = vec![
-        (Vec3::new(-1.25, 1.25, 0.0), q, z_cuboid.clone()),
-        (Vec3::new(1.25, 1.25, 0.0), q, z_cuboid.clone()),
-        (Vec3::new(1.25, -1.25, 0.0), q, z_cuboid.clone()),
-        (Vec3::new(-1.25, -1.25, 0.0), q, z_cuboid.clone()),
-        (Vec3::new(-1.25, 0.0, 1.25), q, y_cuboid.clone()),
-        (Vec3::new(1.25, 0.0, 1.25), q, y_cuboid.clone()),
-        (Vec3::new(1.25, 0.0, -1.25), q, y_cuboid.clone()),
-        (Vec3::new(-1.25, 0.0, -1.25), q, y_cuboid.clone()),
-        (Vec3::new(0.0, -1.25, 1.25), q, x_cuboid.clone()),
-        (Vec3::new(0.0, 1.25, 1.25), q, x_cuboid.clone()),
-        (Vec3::new(0.0, 1.25, -1.25), q, x_cuboid.clone()),
-        (Vec3::new(0.0, -1.25, -1.25), q, x_cuboid.clone()),
-    ];
-
-    let ground1_collider_vec = vec![(Vec3::ZERO, q, Collider::cuboid(1.5, 1.5, 1.5))];
-
-    let spires_solid_collider_vec = vec![(Vec3::ZERO, q, Collider::cuboid(1.5, 1.5, 1.5))];
-
-    let y_cuboid = Collider::cuboid(0.25, 1.25, 0.25);
-    let spires_hollow_collider_vec = vec![
-        (
-            Vec3::new(0.0, 1.25, 0.0),
-            q,
-            Collider::cuboid(1.5, 0.25, 1.5),
-        ),
-        (Vec3::new(-1.25, -0.25, 1.25), q, y_cuboid.clone()),
-        (Vec3::new(1.25, -0.25, 1.25), q, y_cuboid.clone()),
-        (Vec3::new(1.25, -0.25, -1.25), q, y_cuboid.clone()),
-        (Vec3::new(-1.25, -0.25, -1.25), q, y_cuboid.clone()),
-        (Vec3::new(0.0, -1.25, 1.25), q, x_cuboid.clone()),
-        (Vec3::new(0.0, -1.25, -1.25), q, x_cuboid.clone()),
-        (Vec3::new(1.25, -1.25, 0.0), q, z_cuboid.clone()),
-        (Vec3::new(-1.25, -1.25, 0.0), q, z_cuboid.clone()),
-    ];
-
-    let well_collider_vec = {
-        let mut e = hollowground_collider_vec.clone();
-        e.append(&mut vec![
-            (Vec3::ZERO, q, Collider::cylinder(1.5, 0.5)),
-            (
-                Vec3::new(0.0, 0.929, 0.0),
-                q,
-                Collider::cuboid(0.455703, 0.071, 1.5),
-            ),
-            (
-                Vec3::new(0.0, -0.929, 0.0),
-                q,
-                Collider::cuboid(0.455703, 0.071, 1.5),
-            ),
-        ]);
-        e
-    };
-
-    let hollowground_ccb = CompoundColliderBuilder::from_vec(hollowground_collider_vec);
-    let ground1_ccb = CompoundColliderBuilder::from_vec(ground1_collider_vec);
-    let spires_hollow_ccb = CompoundColliderBuilder::from_vec(spires_hollow_collider_vec);
-    let spires_solid_ccb = CompoundColliderBuilder::from_vec(spires_solid_collider_vec);
-    let well_ccb = CompoundColliderBuilder::from_vec(well_collider_vec);
+    let noise = TerrainNoise::new(&seed);
+    // Every random pick goes through this, so a seed always makes the same world
+    let mut rng = StdRng::seed_from_u64(u64::from(seed.0));
 
     let mut blocks = Blocks::new();
+    let mut block_names = HashMap::new();
     let mut results = Vec::new();
 
-    results.push(blocks.add(
-        "models/ground1/ground1.gltf#Mesh0",
-        ground1_ccb,
-        &asset_server,
-        &gltf_meshes,
-    ));
-
-    results.push(blocks.add(
-        "models/ground1/hollow_ground.gltf#Mesh0",
-        hollowground_ccb,
-        &asset_server,
-        &gltf_meshes,
-    ));
-
-    results.push(blocks.add(
-        "models/ground1/spires_hollow.gltf#Mesh0",
-        spires_hollow_ccb,
-        &asset_server,
-        &gltf_meshes,
-    ));
-
-    results.push(blocks.add(
-        "models/ground1/spires_full.gltf#Mesh0",
-        spires_solid_ccb,
-        &asset_server,
-        &gltf_meshes,
-    ));
-
-    results.push(blocks.add(
-        "models/ground1/well_ground.gltf#Mesh0",
-        well_ccb,
-        &asset_server,
-        &gltf_meshes,
-    ));
+    for handle in block_handles.0.iter() {
+        let definition = match block_definitions.get(handle) {
+            Some(e) => e,
+            None => continue,
+        };
+        if blocks.get(&definition.name).is_some() {
+            error!(
+                "{:?}: field `name`: \"{}\" is already defined",
+                asset_server.get_handle_path(handle),
+                definition.name
+            );
+            continue;
+        }
+        // The manifest already said why
+        if !model_manifest.is_loaded(&definition.mesh_path) {
+            continue;
+        }
+        block_names.insert(handle.id, definition.name.clone());
+        results.push(blocks.add(definition, &asset_server, &gltf_meshes));
+    }
 
     for result in results {
         match result {
@@ -170,51 +156,50 @@ pub fn generate_terrain(
     // generates terrain given a width and a length
     for i in 0..generator_options.radius {
         let i_usize = i as usize;
-        let i_i32 = i as i32;
         for j in 0..generator_options.radius {
-            let j_i32 = j as i32;
-            if (((i_i32 - 50) * (i_i32 - 50)) + ((j_i32 - 50) * (j_i32 - 50))) < 2500
-                && world_gen_array[i_usize][50][j as usize].is_none()
-            {
-                let n = perlin.get([(i as f64) * 0.15, (j as f64) * 0.15]);
-
-                if n > 0.0 {
-                    let mut rng = rand::thread_rng();
+            if world_gen_array[i_usize][GROUND_LEVEL][j as usize].is_none() {
+                if let Some(n) = noise.ground(i, j) {
                     let j_usize = j as usize;
 
-                    world_gen_array[i_usize][50][j_usize] =
-                        Some(rng.random_pick(0.5, "ground1", "hollow_ground"));
+                    world_gen_array[i_usize][GROUND_LEVEL][j_usize] =
+                        blocks.pick(GROUND_TAG, &mut rng);
                     if n >= 0.3 {
-                        world_gen_array[i_usize][49][j_usize] =
-                            Some(rng.random_pick(0.5, "ground1", "hollow_ground"));
+                        world_gen_array[i_usize][GROUND_LEVEL - 1][j_usize] =
+                            blocks.pick(GROUND_TAG, &mut rng);
                         if n >= 0.6 {
-                            world_gen_array[i_usize][48][j_usize] =
-                                Some(rng.random_pick(0.5, "ground1", "hollow_ground"));
+                            world_gen_array[i_usize][GROUND_LEVEL - 2][j_usize] =
+                                blocks.pick(GROUND_TAG, &mut rng);
                             if n >= 0.95 {
-                                world_gen_array[i_usize][47][j_usize] =
-                                    Some(rng.random_pick(0.5, "ground1", "hollow_ground"));
+                                world_gen_array[i_usize][GROUND_LEVEL - 3][j_usize] =
+                                    blocks.pick(GROUND_TAG, &mut rng);
                             }
                         }
                     }
 
                     let well_decider = rng.gen_ratio(1, 100);
 
-                    if well_decider {
+                    if let (true, Some(well)) = (well_decider, blocks.pick(WELL_TAG, &mut rng)) {
                         let big_well = rng.gen_ratio(1, 5)
                             && (i_usize > 0 && i_usize < 99 && j_usize > 0 && j_usize < 99);
 
                         if big_well {
-                            generate_well_cluster(&mut world_gen_array, i_usize, j_usize);
+                            generate_well_cluster(
+                                &mut world_gen_array,
+                                i_usize,
+                                j_usize,
+                                well,
+                                &mut rng,
+                            );
                         } else {
-                            generate_well_column(&mut world_gen_array, i_usize, j_usize);
+                            generate_well_column(&mut world_gen_array, i_usize, j_usize, well);
                         }
                     }
 
-                    if spire_perlin.get([(i as f64) * 0.1, (j as f64) * 0.1]) > 0.5 {
+                    if noise.spire(i, j) {
                         let height = rng.gen_range(3..=7);
                         for y in 1..=height {
-                            world_gen_array[i_usize][50 + y][j_usize] =
-                                Some(rng.random_pick(0.5, "spires_hollow", "spires_full"));
+                            world_gen_array[i_usize][GROUND_LEVEL + y][j_usize] =
+                                blocks.pick(SPIRE_TAG, &mut rng);
                         }
                     }
                 }
@@ -231,23 +216,20 @@ pub fn generate_terrain(
             let y_pos = y as f32 * 3.0;
             for (x, i) in row.into_iter().enumerate() {
                 //info!("x: {}, y: {}, z: {}", x, y, z);
-                if i.is_some() {
+                if let Some(name) = i {
                     let x_pos = x as f32 * 3.0;
                     let translation = Vec3::new(x_pos, y_pos, z_pos);
 
-                    let mut data = blocks.get(&i.unwrap()).unwrap().clone();
+                    let mut data = blocks.get(name).unwrap().clone();
 
                     translate_gltf_primitives(&mut data.model.primitives, &mut meshes, translation);
                     primitives.append(&mut data.model.primitives);
                     commands.spawn().insert_bundle((
                         data.collider.build(),
                         Transform::from_translation(translation),
-                        CollisionGroups {
-                            memberships: 0b00000001,
-                            filters: 0b11111110,
-                        },
+                        CollisionLayers::of(CollisionLayer::Terrain).groups(),
                         ActiveCollisionTypes::STATIC_STATIC,
-                        TerrainBlockName(i.unwrap()),
+                        TerrainBlockName(name.to_string()),
                     ));
                 }
             }
@@ -257,29 +239,71 @@ pub fn generate_terrain(
     let bundle = combine_gltf_mesh(primitives, &mut meshes, &mut materials, &mut images);
 
     commands.spawn_bundle(bundle);
+    commands.insert_resource(blocks);
+    commands.insert_resource(TerrainBlockNames(block_names));
 
     info!("Generation time: {:?}", time.elapsed());
 
     done.done = true;
 }
 
-fn generate_well_cluster(world: &mut Vec<Vec<Vec<Option<&str>>>>, x: usize, z: usize) {
-    let mut rng = rand::thread_rng();
+/// Where the player spawns when they don't have a spawn point
+///
+/// On top of the ground column closest to the middle of the terrain that doesn't have a spire on it.
+/// Uses the same noise as `generate_terrain`, so it works before the terrain colliders exist
+pub fn default_spawn_point(generator_options: &GeneratorOptions, seed: &TerrainSeed) -> Vec3 {
+    let noise = TerrainNoise::new(seed);
+
+    let mut closest: Option<(i32, u32, u32)> = None;
+    for i in 0..generator_options.radius {
+        for j in 0..generator_options.radius {
+            let distance = TerrainNoise::distance_squared(i, j);
+            if closest.map_or(false, |e| e.0 <= distance) {
+                continue;
+            }
+
+            if noise.ground(i, j).is_some() && !noise.spire(i, j) {
+                closest = Some((distance, i, j));
+            }
+        }
+    }
+
+    // Blocks are 3 big
+    let (i, j) = closest.map_or((CENTER, CENTER), |(_, i, j)| (i, j));
+    Vec3::new(
+        j as f32 * 3.0,
+        GROUND_LEVEL as f32 * 3.0 + 1.5 + 1.0,
+        i as f32 * 3.0,
+    )
+}
+
+fn generate_well_cluster<'a>(
+    world: &mut [Vec<Vec<Option<&'a str>>>],
+    x: usize,
+    z: usize,
+    block: &'a str,
+    rng: &mut impl Rng,
+) {
     let x_i32 = x as i32;
     let z_i32 = z as i32;
 
     for i in -1..=1 {
         for j in -1..=1 {
             if rng.gen_bool(0.5) {
-                generate_well_column(world, (x_i32 + i) as usize, (z_i32 + j) as usize);
+                generate_well_column(world, (x_i32 + i) as usize, (z_i32 + j) as usize, block);
             }
         }
     }
 }
 
-fn generate_well_column(world: &mut Vec<Vec<Vec<Option<&str>>>>, x: usize, z: usize) {
+fn generate_well_column<'a>(
+    world: &mut [Vec<Vec<Option<&'a str>>>],
+    x: usize,
+    z: usize,
+    block: &'a str,
+) {
     for y in 35..=50 {
-        world[x][y][z] = Some("well_ground");
+        world[x][y][z] = Some(block);
     }
 }
 
@@ -287,7 +311,7 @@ pub trait Pick<T> {
     fn random_pick(&mut self, bias: f32, n1: T, n2: T) -> T;
 }
 
-impl<T> Pick<T> for ThreadRng {
+impl<T, R: Rng> Pick<T> for R {
     // Bias is the bias towards n1
     fn random_pick(&mut self, bias: f32, n1: T, n2: T) -> T {
         if !(0.0 <= bias && bias <= 1.0) {
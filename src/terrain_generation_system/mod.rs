@@ -1,6 +1,13 @@
-use bevy::prelude::{App, Plugin};
+use bevy::prelude::{AddAsset, App, Plugin};
+use rand::{thread_rng, Rng};
 
-use self::generator::{generate_terrain, GeneratorOptions, TerrainGenDone};
+use self::{
+    generator::{generate_terrain, GeneratorOptions, TerrainGenDone, TerrainSeed},
+    terrain_block::{
+        load_terrain_blocks, reload_terrain_blocks, request_terrain_models,
+        TerrainBlockDefinition, TerrainBlockDefinitionLoader,
+    },
+};
 
 pub mod compound_collider_builder;
 pub mod generator;
@@ -16,7 +23,13 @@ impl Plugin for GeneratorPlugin {
             radius: 100,
             height: 1,
         })
+        .insert_resource(TerrainSeed(thread_rng().gen()))
         .insert_resource(TerrainGenDone { done: false })
-        .add_system(generate_terrain);
+        .add_asset::<TerrainBlockDefinition>()
+        .init_asset_loader::<TerrainBlockDefinitionLoader>()
+        .add_startup_system(load_terrain_blocks)
+        .add_system(request_terrain_models)
+        .add_system(generate_terrain)
+        .add_system(reload_terrain_blocks);
     }
 }
@@ -1,19 +1,178 @@
-use bevy::{asset::LoadState, gltf::GltfMesh, prelude::*, utils::HashMap};
+use anyhow::{anyhow, bail};
+use bevy::{
+    asset::{AssetLoader, HandleId, LoadContext, LoadState, LoadedAsset},
+    gltf::GltfMesh,
+    prelude::*,
+    reflect::TypeUuid,
+    utils::{BoxedFuture, HashMap},
+};
+use bevy_rapier3d::prelude::Collider;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::building_system::{building_definitions::ColliderPart, load_models::ModelManifest};
 
 use super::compound_collider_builder::CompoundColliderBuilder;
 
-#[derive(Component, Copy, Clone, PartialEq, Eq, Hash)]
-pub struct TerrainBlockName(pub &'static str);
+/// Folder (in `assets`) every `*.block.ron` file is loaded from
+pub const TERRAIN_BLOCKS_FOLDER: &str = "data/terrain";
+
+/// Blocks that make up the ground layers
+pub const GROUND_TAG: &str = "ground";
+/// Blocks stacked into spires on top of the ground
+pub const SPIRE_TAG: &str = "spire";
+/// Blocks well columns are made of, well pumps snap on top of them
+pub const WELL_TAG: &str = "well";
+
+#[derive(Component, Clone, PartialEq, Eq, Hash)]
+pub struct TerrainBlockName(pub String);
+
+/// A terrain block as it's written in its `*.block.ron` file
+#[derive(Deserialize, TypeUuid, Clone, Debug)]
+#[uuid = "6e2d8c41-3b7f-4a90-8d1e-5f9a0c2b7d63"]
+pub struct TerrainBlockDefinition {
+    /// Must be unique between all files
+    pub name: String,
+    /// GLTF of the block, its first mesh is used
+    pub mesh_path: String,
+    pub colliders: Vec<ColliderPart>,
+    pub weight: f32,
+    /// What the generator uses the block for, see `GROUND_TAG`, `SPIRE_TAG` and `WELL_TAG`
+    pub tags: Vec<String>,
+}
+
+impl TerrainBlockDefinition {
+    /// Checks everything the types can't, the error names the field that's wrong
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("field `name`: can't be empty".to_string());
+        }
+        if !self.mesh_path.ends_with(".gltf") {
+            return Err("field `mesh_path`: must be a .gltf file".to_string());
+        }
+        if self.colliders.is_empty() {
+            return Err("field `colliders`: needs at least one part".to_string());
+        }
+        for (i, part) in self.colliders.iter().enumerate() {
+            part.shape.validate(&format!("colliders[{}].shape", i))?;
+        }
+        if self.weight <= 0.0 {
+            return Err("field `weight`: must be more than 0".to_string());
+        }
+        if self.tags.is_empty() {
+            return Err(
+                "field `tags`: needs at least one tag, untagged blocks are never placed"
+                    .to_string(),
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn collider(&self) -> CompoundColliderBuilder {
+        CompoundColliderBuilder::from_vec(
+            self.colliders
+                .iter()
+                .map(|e| {
+                    (
+                        Vec3::from(e.position),
+                        Quat::IDENTITY,
+                        e.shape.to_collider(),
+                    )
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Default)]
+pub struct TerrainBlockDefinitionLoader;
+
+impl AssetLoader for TerrainBlockDefinitionLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let definition: TerrainBlockDefinition = ron::de::from_bytes(bytes)
+                .map_err(|e| anyhow!("{:?}: {}", load_context.path(), e))?;
+
+            if let Err(e) = definition.validate() {
+                bail!("{:?}: {}", load_context.path(), e);
+            }
+
+            load_context.set_default_asset(LoadedAsset::new(definition));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["block.ron"]
+    }
+}
+
+/// Every file in `TERRAIN_BLOCKS_FOLDER`
+pub struct TerrainBlockHandles(pub Vec<Handle<TerrainBlockDefinition>>);
+
+pub fn load_terrain_blocks(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let handles = match asset_server.load_folder(TERRAIN_BLOCKS_FOLDER) {
+        Ok(e) => e.into_iter().map(|e| e.typed()).collect(),
+        Err(e) => {
+            error!(
+                "Could not load terrain blocks from {:?}: {}",
+                TERRAIN_BLOCKS_FOLDER, e
+            );
+            Vec::new()
+        }
+    };
+
+    commands.insert_resource(TerrainBlockHandles(handles));
+}
+
+/// `false` while any block is still loading or its model hasn't been requested yet
+pub fn terrain_blocks_loaded(
+    asset_server: &AssetServer,
+    handles: &TerrainBlockHandles,
+    definitions: &Assets<TerrainBlockDefinition>,
+    model_manifest: &ModelManifest,
+) -> bool {
+    handles
+        .0
+        .iter()
+        .all(|e| match asset_server.get_load_state(e) {
+            LoadState::Loaded => definitions
+                .get(e)
+                .map_or(false, |e| model_manifest.contains(&e.mesh_path)),
+            LoadState::Failed => true,
+            _ => false,
+        })
+}
+
+/// Adds the model of every loaded terrain block to the manifest
+pub fn request_terrain_models(
+    asset_server: Res<AssetServer>,
+    handles: Res<TerrainBlockHandles>,
+    definitions: Res<Assets<TerrainBlockDefinition>>,
+    mut model_manifest: ResMut<ModelManifest>,
+) {
+    for definition in handles.0.iter().filter_map(|e| definitions.get(e)) {
+        model_manifest.request(&definition.mesh_path, &asset_server);
+    }
+}
 
 #[derive(Clone)]
 pub struct TerrainBlockData {
-    pub path: &'static str,
+    pub path: String,
     pub collider: CompoundColliderBuilder,
     pub model: GltfMesh,
+    pub weight: f32,
+    pub tags: Vec<String>,
 }
 
+/// Every terrain block that can be generated, inserted as a resource once the terrain is generated
 pub struct Blocks {
-    pub hash: HashMap<&'static str, TerrainBlockData>,
+    pub hash: HashMap<String, TerrainBlockData>,
 }
 
 impl Blocks {
@@ -23,37 +182,198 @@ impl Blocks {
         }
     }
 
-    pub fn get(&self, block_name: &'static str) -> Option<&TerrainBlockData> {
+    pub fn get(&self, block_name: &str) -> Option<&TerrainBlockData> {
         self.hash.get(block_name)
     }
 
+    pub fn has_tag(&self, block_name: &str, tag: &str) -> bool {
+        self.get(block_name)
+            .map_or(false, |e| e.tags.iter().any(|e| e == tag))
+    }
+
     pub fn add(
         &mut self,
-        path: &'static str,
-        collider: CompoundColliderBuilder,
+        definition: &TerrainBlockDefinition,
         asset_server: &Res<AssetServer>,
         gltf_meshes: &Res<Assets<GltfMesh>>,
     ) -> Result<(), LoadState> {
+        let path = format!("{}{}", definition.mesh_path, "#Mesh0");
         let model;
-        let load_state = asset_server.get_load_state(path);
+        let load_state = asset_server.get_load_state(path.as_str());
         match load_state {
             LoadState::Loaded => {
-                model = gltf_meshes.get(path).unwrap().clone();
+                model = gltf_meshes.get(path.as_str()).unwrap().clone();
             }
             e => return Result::Err(e),
         }
 
-        let name = path.rsplit('/').next().unwrap().split('.').next().unwrap();
-        info!("{name}");
+        info!("{}", definition.name);
         self.hash.insert(
-            name,
+            definition.name.clone(),
             TerrainBlockData {
-                path,
-                collider,
+                path: definition.mesh_path.clone(),
+                collider: definition.collider(),
                 model,
+                weight: definition.weight,
+                tags: definition.tags.clone(),
             },
         );
 
         Result::Ok(())
     }
+
+    /// Picks a random block with `tag`, blocks with more weight are picked more often
+    pub fn pick(&self, tag: &str, rng: &mut impl Rng) -> Option<&str> {
+        let mut tagged: Vec<(&String, f32)> = self
+            .hash
+            .iter()
+            .filter(|(_, e)| e.tags.iter().any(|e| e == tag))
+            .map(|(name, e)| (name, e.weight))
+            .collect();
+        // The map's order changes between runs, a seeded rng has to see the same list
+        tagged.sort_by(|a, b| a.0.cmp(b.0));
+
+        let total: f32 = tagged.iter().map(|e| e.1).sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut roll = rng.gen_range(0.0..total);
+        for (name, weight) in tagged.iter() {
+            if roll < *weight {
+                return Some(name.as_str());
+            }
+            roll -= weight;
+        }
+        tagged.last().map(|e| e.0.as_str())
+    }
+}
+
+/// The block each definition file made, inserted along with `Blocks`
+pub struct TerrainBlockNames(pub HashMap<HandleId, String>);
+
+/// Applies edits to terrain block files while the game runs (`GameSettings::watch_definitions`)
+///
+/// Weight and tags are updated in `Blocks` and the new collider replaces the old one on every block of
+/// the terrain. The blocks' meshes are combined into a single one when the terrain is generated, so
+/// edits to `name` or `mesh_path` are logged and the last good definition is kept, like with files that
+/// don't parse or validate (the loader logs those)
+pub fn reload_terrain_blocks(
+    mut definition_events: EventReader<AssetEvent<TerrainBlockDefinition>>,
+    definitions: Res<Assets<TerrainBlockDefinition>>,
+    asset_server: Res<AssetServer>,
+    block_names: Option<Res<TerrainBlockNames>>,
+    blocks: Option<ResMut<Blocks>>,
+    mut block_query: Query<(&TerrainBlockName, &mut Collider)>,
+) {
+    let (block_names, mut blocks) = match (block_names, blocks) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return,
+    };
+
+    for event in definition_events.iter() {
+        let handle = match event {
+            AssetEvent::Modified { handle } => handle,
+            _ => continue,
+        };
+        let definition = match definitions.get(handle) {
+            Some(e) => e,
+            None => continue,
+        };
+        let path = asset_server.get_handle_path(handle);
+
+        let name = match block_names.0.get(&handle.id) {
+            Some(e) => e,
+            None => {
+                warn!(
+                    "{:?}: wasn't loaded when the terrain was generated, restart the game to add the block",
+                    path
+                );
+                continue;
+            }
+        };
+        if definition.name != *name {
+            error!(
+                "{:?}: field `name`: can't be changed while the game runs, keeping the last good definition of \"{}\"",
+                path, name
+            );
+            continue;
+        }
+        let data = match blocks.hash.get_mut(name) {
+            Some(e) => e,
+            None => continue,
+        };
+        if definition.mesh_path != data.path {
+            error!(
+                "{:?}: field `mesh_path`: can't be changed while the game runs, keeping the last good definition of \"{}\"",
+                path, name
+            );
+            continue;
+        }
+
+        data.collider = definition.collider();
+        data.weight = definition.weight;
+        data.tags = definition.tags.clone();
+
+        let collider = data.collider.build();
+        let mut refreshed = 0;
+        for (_, mut block_collider) in block_query.iter_mut().filter(|(e, _)| e.0 == *name) {
+            *block_collider = collider.clone();
+            refreshed += 1;
+        }
+
+        info!(
+            "{:?}: reloaded \"{}\", {} terrain blocks refreshed",
+            path, name, refreshed
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    fn blocks(names: &[&str]) -> Blocks {
+        let mut blocks = Blocks::new();
+        for name in names {
+            blocks.hash.insert(
+                name.to_string(),
+                TerrainBlockData {
+                    path: String::new(),
+                    collider: CompoundColliderBuilder::new(),
+                    model: GltfMesh { primitives: vec![] },
+                    weight: 1.0,
+                    tags: vec![GROUND_TAG.to_string()],
+                },
+            );
+        }
+        blocks
+    }
+
+    #[test]
+    fn same_seed_picks_the_same_blocks() {
+        let picks = |blocks: &Blocks| {
+            let mut rng = StdRng::seed_from_u64(7);
+            (0..32)
+                .map(|_| blocks.pick(GROUND_TAG, &mut rng).unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // Inserted in a different order so the maps iterate differently
+        let first = blocks(&["dirt", "rock", "sand", "clay"]);
+        let second = blocks(&["clay", "sand", "rock", "dirt"]);
+
+        assert_eq!(picks(&first), picks(&second));
+    }
+
+    #[test]
+    fn pick_ignores_other_tags() {
+        let blocks = blocks(&["dirt"]);
+        let mut rng = StdRng::seed_from_u64(0);
+
+        assert_eq!(blocks.pick(GROUND_TAG, &mut rng), Some("dirt"));
+        assert_eq!(blocks.pick(WELL_TAG, &mut rng), None);
+    }
 }